@@ -0,0 +1,212 @@
+//! # Complete the values typed by the user
+use crate::collections::{DataBase, ItemCollection};
+use crate::models::{GameTraits, Item};
+use crate::utils::fold_name;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+/// The fields that can be completed.
+pub const COMPLETION_FIELDS: [&str; 7] =
+    ["Game", "Tags", "Genre", "Engine", "Runtime", "Dev", "Pub"];
+
+/* ------------------------ COMPLETION -----------------------*/
+/// # Represent a value completing the beginning typed by the user
+#[derive(Serialize, PartialEq, Debug)]
+pub struct Completion<'a> {
+    /// The value, as written in the database.
+    pub value: &'a str,
+    /// The number of games using the value.
+    pub count: usize,
+}
+
+/// A value and its folded version used for the comparisons.
+struct Entry {
+    key: String,
+    value: String,
+    count: usize,
+}
+
+/// The entries of a field, sorted by key.
+struct Entries(Vec<Entry>);
+
+impl Entries {
+    /// Returns the counted values, sorted by key.
+    fn from_counts(counts: HashMap<&str, usize>) -> Self {
+        let mut entries: Vec<Entry> = counts
+            .into_iter()
+            .map(|(value, count)| Entry {
+                key: fold_name(value),
+                value: value.to_string(),
+                count,
+            })
+            .filter(|entry| !entry.key.is_empty())
+            .collect();
+        entries.sort_by(|left, right| {
+            left.key
+                .cmp(&right.key)
+                .then_with(|| left.value.cmp(&right.value))
+        });
+        Entries(entries)
+    }
+    /// Returns the entries whose key starts with the prefix.
+    fn with_prefix(&self, prefix: &str) -> &[Entry] {
+        let start = self.0.partition_point(|entry| entry.key.as_str() < prefix);
+        let length = self.0[start..]
+            .iter()
+            .take_while(|entry| entry.key.starts_with(prefix))
+            .count();
+        &self.0[start..start + length]
+    }
+}
+
+/* ------------------------ INDEX -----------------------*/
+/// # Represent a prefix index of the values of the database
+/// The game names and the values of the tags, genres, engines, runtimes,
+/// developers and publishers are completed, the ones used by the most
+/// games first. The values are compared ignoring case, punctuation and
+/// diacritics. They are sorted once folded, so that the values starting
+/// with a prefix are found by a binary search.
+/// ```
+/// use pobsdlib::collections::DataBase;
+/// let db = DataBase::open("tests/data/test-games.db").unwrap();
+/// let completions = db.complete("Engine", "fn", 5);
+/// assert_eq!(completions[0].value, "FNA");
+/// assert_eq!(completions[0].count, 2);
+/// ```
+pub struct Completions {
+    fields: HashMap<&'static str, Entries>,
+}
+
+impl Completions {
+    /// Builds the index of the database. The values are weighted by the
+    /// number of games of their item in the database collections, the
+    /// names by the number of games having them.
+    pub fn new(database: &DataBase) -> Self {
+        let mut fields: HashMap<&'static str, Entries> = HashMap::new();
+        for name in &COMPLETION_FIELDS {
+            let entries = match *name {
+                "Game" => Self::count_values(&database.games, name),
+                "Tags" => Self::count_items(&database.tags),
+                "Genre" => Self::count_items(&database.genres),
+                "Engine" => Self::count_items(&database.engines),
+                "Runtime" => Self::count_items(&database.runtimes),
+                "Dev" => Self::count_items(&database.devs),
+                _ => Self::count_items(&database.publishers),
+            };
+            fields.insert(name, entries);
+        }
+        Self { fields }
+    }
+    /// Counts the distinct games of each item, a game being counted once
+    /// even if it has the value twice.
+    fn count_items(items: &ItemCollection<Item>) -> Entries {
+        let mut games: HashMap<&str, HashSet<usize>> = HashMap::new();
+        for item in &items.items {
+            games.entry(&item.name).or_default().extend(&item.games);
+        }
+        let counts: HashMap<&str, usize> = games
+            .into_iter()
+            .map(|(name, games)| (name, games.len()))
+            .collect();
+        Entries::from_counts(counts)
+    }
+    fn count_values<'a, T: GameTraits>(games: &'a ItemCollection<T>, name: &str) -> Entries {
+        let mut counts: HashMap<&'a str, usize> = HashMap::new();
+        for game in &games.items {
+            if let Ok(field) = game.get_field(name) {
+                for value in field.values() {
+                    *counts.entry(value).or_insert(0) += 1;
+                }
+            }
+        }
+        Entries::from_counts(counts)
+    }
+    /// Returns at most `limit` values of the field starting with the
+    /// prefix, the ones used by the most games first, then in alphabetical
+    /// order. The field name is not case sensitive, unknown fields have
+    /// no completion (see `COMPLETION_FIELDS`).
+    pub fn complete(&self, field: &str, prefix: &str, limit: usize) -> Vec<Completion<'_>> {
+        let entries = match COMPLETION_FIELDS
+            .iter()
+            .find(|name| name.eq_ignore_ascii_case(field))
+            .and_then(|name| self.fields.get(name))
+        {
+            Some(entries) => entries,
+            None => return Vec::new(),
+        };
+        let prefix = fold_name(prefix);
+        // the entries are in alphabetical order, the sort is stable
+        let mut found: Vec<&Entry> = entries.with_prefix(&prefix).iter().collect();
+        found.sort_by_key(|entry| Reverse(entry.count));
+        found
+            .into_iter()
+            .take(limit)
+            .map(|entry| Completion {
+                value: &entry.value,
+                count: entry.count,
+            })
+            .collect()
+    }
+}
+
+/*-------------------------- TESTS --------------------------------*/
+#[cfg(test)]
+mod test_completions {
+    use super::*;
+    use utils::load_test_games;
+    fn database() -> DataBase {
+        DataBase::from_games(
+            load_test_games(
+                "Game\tPokémon\nEngine\tUnity\nTags\trpg, indie\n\
+                 Game\tPoker Night\nEngine\tUnity\nTags\tcards\n\
+                 Game\tPortal\nEngine\tSource\nTags\tindie, puzzle\n\
+                 Game\tShuggy\nEngine\tFNA\nTags\tindie",
+            )
+            .items,
+        )
+    }
+    #[test]
+    fn prefixes() {
+        let completions = Completions::new(&database());
+        let names: Vec<&str> = completions
+            .complete("Game", "POKE", 5)
+            .iter()
+            .map(|c| c.value)
+            .collect();
+        assert_eq!(names, vec!["Pokémon", "Poker Night"]);
+        assert_eq!(completions.complete("game", "", 10).len(), 4);
+        assert!(completions.complete("Game", "x", 10).is_empty());
+        assert!(completions.complete("Unknown", "", 10).is_empty());
+    }
+    #[test]
+    fn weighted_by_games() {
+        let completions = Completions::new(&database());
+        assert_eq!(
+            completions.complete("Tags", "", 2),
+            vec![
+                Completion {
+                    value: "indie",
+                    count: 3
+                },
+                Completion {
+                    value: "cards",
+                    count: 1
+                }
+            ]
+        );
+        assert_eq!(completions.complete("Engine", "u", 1)[0].count, 2);
+    }
+    #[test]
+    fn repeated_values() {
+        let database = DataBase::from_games(
+            load_test_games(
+                "Game\tToto\nTags\tindie, indie\nGenre\tRPG, RPG\n\
+                 Game\tTiti\nTags\tindie",
+            )
+            .items,
+        );
+        let completions = Completions::new(&database);
+        assert_eq!(completions.complete("Tags", "ind", 1)[0].count, 2);
+        assert_eq!(completions.complete("Genre", "rpg", 1)[0].count, 1);
+    }
+}
@@ -1,22 +1,70 @@
 extern crate pobsdlib;
 extern crate serde_json;
 use pobsdlib::collections::DataBase;
-use std::{env, path, process};
+use pobsdlib::export::{source_hash, Export, Sections};
+use pobsdlib::Error;
+use std::{env, fs, path, process};
+
+const USAGE: &str = "usage: database2json [--games] [--tags] [--genres] database
+By default, all the sections are written.";
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        eprintln!("Not enough arguments");
-        process::exit(1);
+    let mut sections = Sections::none();
+    let mut filename: Option<String> = None;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--games" => sections.games = true,
+            "--tags" => sections.tags = true,
+            "--genres" => sections.genres = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with('-') => {
+                eprintln!("Unknown option {}\n{}", arg, USAGE);
+                process::exit(1);
+            }
+            _ if filename.is_some() => {
+                eprintln!("Too many arguments\n{}", USAGE);
+                process::exit(1);
+            }
+            _ => filename = Some(arg),
+        }
     }
-    if args.len() > 2 {
-        eprintln!("Too many arguments");
-        process::exit(1);
+    let filename = match filename {
+        Some(filename) => filename,
+        None => {
+            eprintln!("Not enough arguments\n{}", USAGE);
+            process::exit(1);
+        }
+    };
+    if sections == Sections::none() {
+        sections = Sections::default();
     }
-    let path = path::Path::new(&args[1]);
+    let path = path::Path::new(&filename);
     if path.is_file() {
-        let db_game = DataBase::new(&args[1]);
-        let json_games = serde_json::to_string_pretty(&db_game.games).unwrap();
+        // the file is read once, to be both hashed and parsed
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(1);
+            }
+        };
+        let db_game = match DataBase::from_reader(bytes.as_slice()) {
+            Ok(db_game) => db_game,
+            Err(Error::Parse(error)) => {
+                eprintln!("{}", error.with_file(&filename));
+                process::exit(1);
+            }
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(1);
+            }
+        };
+        let hash = source_hash(bytes.as_slice()).unwrap();
+        let export = Export::new(&db_game, sections).with_source_hash(&hash);
+        let json_games = serde_json::to_string_pretty(&export).unwrap();
         println!("{}", json_games);
     } else {
         eprintln!("This is not a file");
@@ -1,28 +1,120 @@
 extern crate pobsdlib;
 extern crate serde_json;
 use pobsdlib::collections::DataBase;
+use pobsdlib::query::Query;
+use pobsdlib::sorting::{SortKey, SortOrder};
 use std::{env, path, process};
 
+const USAGE: &str = "usage: pobsdsearch [options] database query
+       pobsdsearch [options] database field value
+       pobsdsearch database --text words
+options:
+       --sort key      sort by name, year, engine, dev, added or updated
+       --desc          sort in descending order
+       --offset n      skip the first n games
+       --limit n       print at most n games";
+
+/// How the games found are printed.
+struct Options {
+    sort: Option<SortKey>,
+    order: SortOrder,
+    offset: Option<usize>,
+    limit: Option<usize>,
+}
+
+fn exit_with_usage(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    process::exit(1);
+}
+
+/// Removes the options from the arguments.
+fn parse_options(args: &mut Vec<String>) -> Options {
+    let mut options = Options {
+        sort: None,
+        order: SortOrder::Ascending,
+        offset: None,
+        limit: None,
+    };
+    let mut index = 1;
+    while index < args.len() {
+        let flag = args[index].clone();
+        let value = |args: &mut Vec<String>| {
+            if index + 1 >= args.len() {
+                exit_with_usage(&format!("Missing value for {}", flag));
+            }
+            args.remove(index + 1)
+        };
+        match flag.as_str() {
+            "--sort" => match value(args).parse() {
+                Ok(key) => options.sort = Some(key),
+                Err(error) => exit_with_usage(&error.to_string()),
+            },
+            "--offset" | "--limit" => match value(args).parse() {
+                Ok(number) if flag == "--offset" => options.offset = Some(number),
+                Ok(number) => options.limit = Some(number),
+                Err(_) => exit_with_usage(&format!("{} expects a number", flag)),
+            },
+            "--desc" => options.order = SortOrder::Descending,
+            _ => {
+                index += 1;
+                continue;
+            }
+        }
+        args.remove(index);
+    }
+    options
+}
+
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 4 {
-        eprintln!("Not enough arguments");
-        process::exit(1);
+    let mut args: Vec<String> = env::args().collect();
+    let options = parse_options(&mut args);
+    if args.len() < 3 {
+        exit_with_usage("Not enough arguments");
     }
     if args.len() > 4 {
-        eprintln!("Too many arguments");
-        process::exit(1);
+        exit_with_usage("Too many arguments");
     }
     let path = path::Path::new(&args[1]);
     if path.is_file() {
-        let db_game = DataBase::new(&args[1]);
-        let field_name = &args[2];
-        let field_value = &args[3];
-        //let json_games = serde_json::to_string_pretty(&db_game.get_games_by_tag(tag)).unwrap();
-        let json_games = serde_json::to_string_pretty(
-            &db_game.games.get_item_with_field(field_name, field_value),
-        )
-        .unwrap();
+        let db_game = match DataBase::open(&args[1]) {
+            Ok(db_game) => db_game,
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(1);
+            }
+        };
+        if args[2] == "--text" {
+            if args.len() != 4 {
+                exit_with_usage("Not enough arguments");
+            }
+            let results = db_game.search_games(&args[3]);
+            println!("{}", serde_json::to_string_pretty(&results).unwrap());
+            return;
+        }
+        let mut games = if args.len() == 3 {
+            match Query::parse(&args[2]) {
+                Ok(query) => db_game.get_games_by_query(&query),
+                Err(error) => {
+                    eprintln!("{}\n{}", error, args[2]);
+                    process::exit(1);
+                }
+            }
+        } else {
+            db_game.games.get_item_with_field(&args[2], &args[3])
+        };
+        // --desc alone sorts by name
+        if options.sort.is_some() || options.order == SortOrder::Descending {
+            games.sort_items(options.sort.unwrap_or_default(), options.order);
+        }
+        let json_games = if options.offset.is_some() || options.limit.is_some() {
+            let page = games.into_page(
+                options.offset.unwrap_or(0),
+                options.limit.unwrap_or(usize::MAX),
+            );
+            serde_json::to_string_pretty(&page).unwrap()
+        } else {
+            serde_json::to_string_pretty(&games).unwrap()
+        };
         println!("{}", json_games);
     } else {
         eprintln!("This is not a file");
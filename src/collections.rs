@@ -1,68 +1,329 @@
-use crate::models::{Game, GameTraits, Item, ItemTraits, ItemTraitsMut};
-use crate::utils::{load_database, load_genres_from_games, load_tags_from_games};
+use crate::autocomplete::{Completion, Completions};
+use crate::error::Error;
+use crate::export::SCHEMA_VERSION;
+use crate::facets::{count_values, Facet, FacetCount, Facets};
+use crate::matching::{MatchMode, Pattern};
+use crate::models::{
+    Game, GameId, GameRef, GameTraits, IgdbId, Item, ItemTraits, ItemTraitsMut, FIELD_NAMES,
+};
+use crate::parser::{Diagnostic, GameRefReader, ParseMode, ParseOptions};
+use crate::query::Query;
+use crate::search::{FuzzyMatch, FuzzyMatcher, SearchIndex, SearchResult, MIN_SIMILARITY};
+use crate::sorting::{Page, SortKey, SortOrder, SortValue};
+use crate::utils::{
+    decode_text, load_database, load_database_from_reader, load_items_from_games, move_position,
+    StableIds,
+};
+use serde::de::{self, Deserialize, Deserializer};
+use serde_json;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// How the names are compared when looking up an item by name.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum NameMatching {
+    /// The names must be equal.
+    #[default]
+    Exact,
+    /// The names must be equal once case folded (Unicode aware).
+    IgnoreCase,
+}
+
+impl NameMatching {
+    /// Returns the key used to index the name.
+    fn key<'a>(self, name: &'a str) -> Cow<'a, str> {
+        match self {
+            NameMatching::Exact => Cow::Borrowed(name),
+            NameMatching::IgnoreCase => Cow::Owned(name.to_lowercase()),
+        }
+    }
+}
 
 /// This collection can store items or games.
 /// When used with items, ItemTraits are also needed.
 /// When used with games, both ItemTraits and GameTraits are needed.
-#[derive(Serialize, Default, Debug)]
+///
+/// The ids of the items do not need to follow their position: they can
+/// be sparse, for instance after an item was removed or when the
+/// collection is a subset of another one.
+///
+/// The collection keeps an index of the ids and of the names so that
+/// looking up an item does not scan the items. The index is kept up to
+/// date by `add_item`, `remove_item` and the references returned by
+/// `get_item_by_id_mut` and `get_item_by_name_mut`. If the items are
+/// changed in another way (e.g. through `items`), `reindex` must be
+/// called. Collections created with `new` and items pushed directly into
+/// `items` are still found, at the cost of a scan, until `reindex` is
+/// called.
+/// ```
+/// use pobsdlib::collections::{ItemCollection, NameMatching};
+/// use pobsdlib::models::Item;
+/// let mut tags: ItemCollection<Item> =
+///     ItemCollection::default().with_name_matching(NameMatching::IgnoreCase);
+/// let mut tag = Item::new();
+/// tag.name = "Indie".to_string();
+/// tags.add_item(tag);
+/// assert_eq!(tags.get_item_by_name("INDIE").unwrap().name, "Indie");
+/// ```
+#[derive(Serialize, Deserialize, Default, Debug)]
 pub struct ItemCollection<T> {
     pub count: usize,
     pub items: Vec<T>,
+    /// Positions of the items having a given name key, in increasing order.
+    #[serde(skip)]
+    names: HashMap<String, Vec<usize>>,
+    /// Positions of the items having a given id, in increasing order.
+    #[serde(skip)]
+    ids: HashMap<usize, Vec<usize>>,
+    /// Number of items taken into account by the indexes.
+    #[serde(skip)]
+    indexed: usize,
+    /// Highest id known, used to give ids to new items.
+    #[serde(skip)]
+    last_id: usize,
+    #[serde(skip)]
+    matching: NameMatching,
 }
 
 impl<T> ItemCollection<T> {
+    /// Create a collection from a vector of items. The items are not
+    /// indexed until `reindex` is called.
     pub fn new(items: Vec<T>) -> Self {
         Self {
             count: items.len(),
             items,
+            names: HashMap::new(),
+            ids: HashMap::new(),
+            indexed: 0,
+            last_id: 0,
+            matching: NameMatching::Exact,
         }
     }
+    /// Returns how the names are compared.
+    pub fn name_matching(&self) -> NameMatching {
+        self.matching
+    }
+    /// Returns true if all the items are indexed.
+    fn is_indexed(&self) -> bool {
+        self.indexed == self.items.len()
+    }
 }
 
 impl<T: ItemTraits> ItemCollection<T> {
+    /// Sets how the names are compared and reindexes the collection.
+    pub fn with_name_matching(mut self, matching: NameMatching) -> Self {
+        self.matching = matching;
+        self.reindex();
+        self
+    }
+    /// Rebuilds the indexes from the items.
+    pub fn reindex(&mut self) {
+        self.names.clear();
+        self.ids.clear();
+        for (position, item) in self.items.iter().enumerate() {
+            let key = self.matching.key(item.get_name()).into_owned();
+            self.names.entry(key).or_default().push(position);
+            self.ids.entry(item.get_id()).or_default().push(position);
+            self.last_id = self.last_id.max(item.get_id());
+        }
+        self.indexed = self.items.len();
+    }
+    /// Returns the position of the first item with the given id.
+    fn position_by_id(&self, id: usize) -> Option<usize> {
+        if id == 0 {
+            return None;
+        }
+        if self.is_indexed() {
+            match self.ids.get(&id).and_then(|positions| positions.first()) {
+                Some(&position) if self.items[position].get_id() == id => return Some(position),
+                Some(_) => (),
+                None => return None,
+            }
+        }
+        self.items.iter().position(|item| item.get_id() == id)
+    }
+    /// Returns the position of the first item with the given name.
+    fn position_by_name(&self, name: &str) -> Option<usize> {
+        let key = self.matching.key(name);
+        if self.is_indexed() {
+            match self
+                .names
+                .get(key.as_ref())
+                .and_then(|positions| positions.first())
+            {
+                Some(&position) if self.matching.key(self.items[position].get_name()) == key => {
+                    return Some(position)
+                }
+                Some(_) => (),
+                None => return None,
+            }
+        }
+        self.items
+            .iter()
+            .position(|item| self.matching.key(item.get_name()) == key)
+    }
     /// Returns a refrence the item corresponding to the id if it exists, None otherwise.
+    /// Ids start at 1, None is returned for 0.
     pub fn get_item_by_id(&self, id: usize) -> Option<&T> {
-        match self.items.get(id - 1) {
-            Some(item) => Some(item),
-            None => None,
-        }
+        let position = self.position_by_id(id)?;
+        self.items.get(position)
     }
     /// Returns a reference the item corresponding to the name if it exists, None otherwise.
     pub fn get_item_by_name(&self, name: &str) -> Option<&T> {
-        // assumre there is only one element with a given name
-        match self.items.iter().find(|&item| item.get_name() == name) {
-            Some(item) => Some(item),
-            None => None,
-        }
+        // assume there is only one element with a given name
+        let position = self.position_by_name(name)?;
+        self.items.get(position)
+    }
+    /// Removes the item corresponding to the id and returns it if it exists.
+    /// The ids of the other items do not change.
+    pub fn remove_item(&mut self, id: usize) -> Option<T> {
+        let position = self.position_by_id(id)?;
+        let item = self.items.remove(position);
+        self.count -= 1;
+        self.reindex();
+        Some(item)
+    }
+    /// Returns at most `limit` items whose name is similar to the given one,
+    /// the most similar first (see `search::FuzzyMatcher`). Items less
+    /// similar than `search::MIN_SIMILARITY` are left out, items equally
+    /// similar are given in the collection order.
+    pub fn find_items_fuzzy(&self, name: &str, limit: usize) -> Vec<FuzzyMatch<'_, T>> {
+        let matcher = FuzzyMatcher::new(name);
+        let mut matches: Vec<FuzzyMatch<'_, T>> = self
+            .items
+            .iter()
+            .map(|item| FuzzyMatch {
+                item,
+                similarity: matcher.similarity(item.get_name()),
+            })
+            .filter(|found| found.similarity >= MIN_SIMILARITY)
+            .collect();
+        // the sort is stable
+        matches.sort_by(|left, right| {
+            right
+                .similarity
+                .partial_cmp(&left.similarity)
+                .unwrap_or(Ordering::Equal)
+        });
+        matches.truncate(limit);
+        matches
     }
 }
 
 impl<T: ItemTraits + ItemTraitsMut> ItemCollection<T> {
     /// Adds an item, set and returns the item id.
+    /// The id follows the highest id of the collection, so that the id of a
+    /// removed item is not given again.
     pub fn add_item(&mut self, mut item: T) -> usize {
+        if !self.is_indexed() {
+            self.reindex();
+        }
+        let id = self.last_id.max(self.count) + 1;
+        item.set_id(id);
+        let key = self.matching.key(item.get_name()).into_owned();
+        self.names.entry(key).or_default().push(self.items.len());
+        self.ids.entry(id).or_default().push(self.items.len());
+        self.indexed += 1;
+        self.last_id = id;
         self.count += 1;
-        item.set_id(self.count);
         self.items.push(item);
-        self.count
+        id
     }
     /// Returns a mutable refrence the item corresponding to the id if it exists, None otherwise.
-    pub fn get_item_by_id_mut(&mut self, id: usize) -> Option<&mut T> {
-        match self.items.get_mut(id - 1) {
-            Some(item) => Some(item),
-            None => None,
-        }
+    /// The indexes are updated when the reference is dropped (see `ItemMut`).
+    pub fn get_item_by_id_mut(&mut self, id: usize) -> Option<ItemMut<'_, T>> {
+        let position = self.position_by_id(id)?;
+        Some(ItemMut::new(self, position))
     }
     /// Returns a mutable reference the item corresponding to the name if it exists, None otherwise.
-    pub fn get_item_by_name_mut(&mut self, name: &str) -> Option<&mut T> {
+    /// The indexes are updated when the reference is dropped (see `ItemMut`).
+    pub fn get_item_by_name_mut(&mut self, name: &str) -> Option<ItemMut<'_, T>> {
         // assume there is only one element with a given name
-        match self.items.iter_mut().find(|item| item.get_name() == name) {
-            Some(item) => Some(item),
-            None => None,
+        let position = self.position_by_name(name)?;
+        Some(ItemMut::new(self, position))
+    }
+}
+
+/* ------------------------ ITEM MUT -----------------------*/
+/// # A mutable reference to an item of an ItemCollection
+/// The name and the id of the item are compared when the reference is
+/// dropped, and the indexes of the collection are updated if they changed.
+/// ```
+/// use pobsdlib::collections::ItemCollection;
+/// use pobsdlib::models::Item;
+/// let mut tags: ItemCollection<Item> = ItemCollection::default();
+/// let mut tag = Item::new();
+/// tag.name = "Indie".to_string();
+/// tags.add_item(tag);
+/// tags.get_item_by_name_mut("Indie").unwrap().name = "indie".to_string();
+/// assert!(tags.get_item_by_name("Indie").is_none());
+/// assert_eq!(tags.get_item_by_name("indie").unwrap().id, 1);
+/// ```
+pub struct ItemMut<'a, T: ItemTraits> {
+    collection: &'a mut ItemCollection<T>,
+    position: usize,
+    /// The name key and the id of the item in the indexes.
+    indexed_key: String,
+    indexed_id: usize,
+}
+
+impl<'a, T: ItemTraits> ItemMut<'a, T> {
+    fn new(collection: &'a mut ItemCollection<T>, position: usize) -> Self {
+        let item = &collection.items[position];
+        let indexed_key = collection.matching.key(item.get_name()).into_owned();
+        let indexed_id = item.get_id();
+        Self {
+            collection,
+            position,
+            indexed_key,
+            indexed_id,
+        }
+    }
+}
+
+impl<'a, T: ItemTraits> Deref for ItemMut<'a, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.collection.items[self.position]
+    }
+}
+
+impl<'a, T: ItemTraits> DerefMut for ItemMut<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.collection.items[self.position]
+    }
+}
+
+impl<'a, T: ItemTraits> Drop for ItemMut<'a, T> {
+    /// Moves the position of the item in the indexes if its name or id changed.
+    fn drop(&mut self) {
+        let collection = &mut *self.collection;
+        if !collection.is_indexed() {
+            return;
+        }
+        let item = &collection.items[self.position];
+        let key = collection.matching.key(item.get_name());
+        if key != self.indexed_key.as_str() {
+            let key = key.into_owned();
+            move_position(&mut collection.names, &self.indexed_key, key, self.position);
+        }
+        let id = item.get_id();
+        if id != self.indexed_id {
+            move_position(&mut collection.ids, &self.indexed_id, id, self.position);
+            collection.last_id = collection.last_id.max(id);
         }
     }
 }
 
 impl<T: GameTraits> ItemCollection<T> {
+    /// Returns a vector of references to items whose field contains the value
+    /// (see `GameTraits::field_contains`).
     pub fn get_item_with_field(
         &self,
         field_name: &str,
@@ -78,21 +339,174 @@ impl<T: GameTraits> ItemCollection<T> {
         }
         ItemCollection::new(games)
     }
-    /// Returns a vector of references to items corresponding to the tag.
+    /// Returns a vector of references to items whose field matches the pattern
+    /// (see `matching`).
+    /// ```
+    /// use pobsdlib::collections::DataBase;
+    /// use pobsdlib::matching::{MatchMode, Pattern};
+    /// let db: DataBase = "Game\tToto\nTags\tnonindie\nGame\tTiti\nTags\tindie".parse().unwrap();
+    /// let pattern = Pattern::new("indie", MatchMode::WholeWord).unwrap();
+    /// let games = db.games.get_item_with_field_matching("Tags", &pattern);
+    /// assert_eq!(games.count, 1);
+    /// assert_eq!(games.items[0].name, "Titi");
+    /// ```
+    pub fn get_item_with_field_matching(
+        &self,
+        field_name: &str,
+        pattern: &Pattern,
+    ) -> ItemCollection<&T> {
+        ItemCollection::new(
+            self.items
+                .iter()
+                .filter(|&item| item.field_matches(field_name, pattern))
+                .collect(),
+        )
+    }
+    /// Returns a vector of references to items matching the query.
+    pub fn get_item_with_query(&self, query: &Query) -> ItemCollection<&T> {
+        ItemCollection::new(
+            self.items
+                .iter()
+                .filter(|&item| query.matches(item))
+                .collect(),
+        )
+    }
+    /// Returns a vector of references to items having the tag (not case sensitive).
     pub fn get_item_with_tag(&self, tag_name: &str) -> ItemCollection<&T> {
-        self.get_item_with_field("Tags", tag_name)
+        self.get_item_with_mode("Tags", tag_name, MatchMode::Exact)
     }
-    /// Returns a vector of references to items corresponding to the genre.
+    /// Returns a vector of references to items having the genre (not case sensitive).
     pub fn get_item_with_genre(&self, genre_name: &str) -> ItemCollection<&T> {
-        self.get_item_with_field("Genre", genre_name)
+        self.get_item_with_mode("Genre", genre_name, MatchMode::Exact)
+    }
+    /// Returns the number of games using each value of the facet, the most
+    /// used first (see `facets`).
+    pub fn get_facet(&self, facet: Facet) -> Vec<FacetCount> {
+        count_values(&self.items, facet)
+    }
+    /// Returns the counts of all the facets (see `facets`).
+    pub fn get_facets(&self) -> Facets {
+        Facets::new(&self.items)
+    }
+    /// Same as get_item_with_field_matching for modes other than MatchMode::Regex.
+    fn get_item_with_mode(
+        &self,
+        field_name: &str,
+        value: &str,
+        mode: MatchMode,
+    ) -> ItemCollection<&T> {
+        match Pattern::new(value, mode) {
+            Ok(pattern) => self.get_item_with_field_matching(field_name, &pattern),
+            Err(_) => ItemCollection::new(Vec::new()),
+        }
+    }
+}
+
+impl<T: GameTraits + ItemTraits> ItemCollection<T> {
+    /// Sorts the games by the key, in the given order (see `SortKey`).
+    /// ```
+    /// use pobsdlib::collections::DataBase;
+    /// use pobsdlib::sorting::{SortKey, SortOrder};
+    /// let db: DataBase = "Game\tThe B\nGame\tC\nGame\tA Z".parse().unwrap();
+    /// let mut games = db.games.get_item_with_field("Game", "");
+    /// games.sort_items(SortKey::Name, SortOrder::Ascending);
+    /// let names: Vec<&str> = games.items.iter().map(|game| game.name.as_str()).collect();
+    /// assert_eq!(names, vec!["The B", "C", "A Z"]);
+    /// ```
+    pub fn sort_items(&mut self, key: SortKey, order: SortOrder) {
+        let indexed = self.is_indexed();
+        let mut sorted: Vec<(SortValue, T)> = self
+            .items
+            .drain(..)
+            .map(|item| (key.sort_value(&item), item))
+            .collect();
+        sorted.sort_by(|left, right| left.0.compare(&right.0, order));
+        self.items = sorted.into_iter().map(|(_, item)| item).collect();
+        if indexed {
+            self.reindex();
+        }
+    }
+}
+
+impl ItemCollection<Item> {
+    /// Builds the taxonomy of a field of the games: one item per value of
+    /// the field, keeping the ids of the games having this value. Fields
+    /// with several values (e.g. Tags) give one item per value, empty values
+    /// are left out. Unknown fields give an empty collection.
+    /// ```
+    /// use pobsdlib::collections::{DataBase, ItemCollection};
+    /// let db: DataBase = "Game\tToto\nEngine\tFNA\nGame\tTiti\nEngine\tFNA".parse().unwrap();
+    /// let engines = ItemCollection::from_field(&db.games, "Engine");
+    /// assert_eq!(engines.count, 1);
+    /// assert_eq!(engines.get_item_by_name("FNA").unwrap().games, vec![1, 2]);
+    /// ```
+    pub fn from_field<T: GameTraits + ItemTraits>(
+        games: &ItemCollection<T>,
+        field_name: &str,
+    ) -> Self {
+        let mut items = ItemCollection::default();
+        load_items_from_games(&mut items, games, |game| match game.get_field(field_name) {
+            Ok(field) => field
+                .values()
+                .into_iter()
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(|value| value.to_string())
+                .collect(),
+            Err(_) => Vec::new(),
+        });
+        items
+    }
+    /// Returns the name of each item with its number of games, the most
+    /// popular first, then in alphabetical order. This works for every
+    /// taxonomy of the database (tags, genres, engines...).
+    /// ```
+    /// use pobsdlib::collections::DataBase;
+    /// let db = DataBase::open("tests/data/test-games.db").unwrap();
+    /// let engines = db.engines.get_name_counts();
+    /// assert_eq!(engines[0], ("FNA", 2));
+    /// assert_eq!(engines[1], ("XNA", 2));
+    /// ```
+    pub fn get_name_counts(&self) -> Vec<(&str, usize)> {
+        let mut counts: Vec<(&str, usize)> = self
+            .items
+            .iter()
+            .map(|item| (item.name.as_str(), item.games.len()))
+            .collect();
+        counts.sort_by(|left, right| right.1.cmp(&left.1).then(left.0.cmp(right.0)));
+        counts
+    }
+    /// Builds the taxonomy of the values counted by the facet (see
+    /// `facets::Facet`), e.g. the store hosts or the decades.
+    pub fn from_facet<T: GameTraits + ItemTraits>(games: &ItemCollection<T>, facet: Facet) -> Self {
+        let mut items = ItemCollection::default();
+        load_items_from_games(&mut items, games, |game| facet.values(game));
+        items
+    }
+}
+
+impl<T> ItemCollection<T> {
+    /// Returns the page of at most `limit` items starting at `offset`,
+    /// along with the total number of items.
+    pub fn into_page(self, offset: usize, limit: usize) -> Page<T> {
+        let total = self.items.len();
+        let items: Vec<T> = self.items.into_iter().skip(offset).take(limit).collect();
+        Page {
+            total,
+            offset,
+            count: items.len(),
+            items,
+        }
     }
 }
 
 /// # DataBase
-/// Store the game database in three different collection:
+/// Store the game database in different collections:
 /// - a games collection
 /// - a tags collection
 /// - a genres collection
+/// - the engines, runtimes, developers, publishers, years and store hosts
+///   collections, built like the tags and genres collections
 ///
 /// Each collection stores items (being games, tags or genres) using the
 /// follwing struct:
@@ -106,6 +520,7 @@ impl<T: GameTraits> ItemCollection<T> {
 /// The games collection also stores a vector of games, each game
 /// being described using the following struct:
 /// ```
+/// # use pobsdlib::models::{Date, ExtraFields, IgdbId};
 /// pub struct Game {
 ///     pub id: usize,
 ///     pub name: String,
@@ -122,11 +537,15 @@ impl<T: GameTraits> ItemCollection<T> {
 ///     pub publi: String,
 ///     pub version: String,
 ///     pub status: String,
+///     pub added: Option<Date>,
+///     pub updated: Option<Date>,
+///     pub igdb_id: Option<IgdbId>,
+///     pub extra: ExtraFields,
 /// }
 /// ```
 ///
-/// The tags/genres collection also stores a vector of tags/genres, each
-/// tag/genre being described by the following struct:
+/// The other collections store a vector of tags, genres, engines..., each
+/// of them being described by the following struct:
 /// ```
 /// pub struct Item {
 ///     pub id: usize,
@@ -135,6 +554,10 @@ impl<T: GameTraits> ItemCollection<T> {
 /// }
 /// ```
 ///
+/// The database can be exported to JSON with serde and read back with
+/// `DataBase::from_json` (see below). Only the games, tags and genres
+/// collections are exported.
+#[derive(Serialize)]
 pub struct DataBase {
     /// Store the games collection (see above for details).
     pub games: ItemCollection<Game>,
@@ -142,23 +565,165 @@ pub struct DataBase {
     pub tags: ItemCollection<Item>,
     /// Store the genres collection (see above for details).
     pub genres: ItemCollection<Item>,
+    /// Store the engines collection.
+    #[serde(skip)]
+    pub engines: ItemCollection<Item>,
+    /// Store the runtimes collection.
+    #[serde(skip)]
+    pub runtimes: ItemCollection<Item>,
+    /// Store the developers collection.
+    #[serde(skip)]
+    pub devs: ItemCollection<Item>,
+    /// Store the publishers collection.
+    #[serde(skip)]
+    pub publishers: ItemCollection<Item>,
+    /// Store the release years collection.
+    #[serde(skip)]
+    pub years: ItemCollection<Item>,
+    /// Store the store hosts collection (see `facets::Facet::Store`).
+    #[serde(skip)]
+    pub stores: ItemCollection<Item>,
+    /// The stable ids of the games.
+    #[serde(skip)]
+    stable_ids: StableIds,
+    /// The completions, built on first use.
+    #[serde(skip)]
+    completions: OnceLock<Completions>,
 }
 
 /// Public API
 impl DataBase {
-    /// Create a database from a file
+    /// Create a database from a file, in lenient mode.
+    /// The lines that cannot be parsed are skipped, and an empty database
+    /// is returned if the file cannot be read. Use `DataBase::open` to get
+    /// the errors instead.
+    #[deprecated(note = "use DataBase::open, which returns the errors")]
     pub fn new(filename: &str) -> Self {
+        let options = ParseOptions {
+            mode: ParseMode::Lenient,
+        };
+        match Self::open_with_options(filename, &options) {
+            Ok((database, _)) => database,
+            Err(_) => Self::from_games(Vec::new()),
+        }
+    }
+    /// Create a database from a file.
+    /// Returns an error if the file cannot be read, is not valid UTF-8
+    /// or cannot be parsed.
+    /// ```
+    /// use pobsdlib::collections::DataBase;
+    /// let db = DataBase::open("tests/data/test-games.db").unwrap();
+    /// assert_eq!(db.get_games_count(), 8);
+    /// ```
+    pub fn open(filename: &str) -> Result<Self, Error> {
+        let (database, _) = Self::open_with_options(filename, &ParseOptions::default())?;
+        Ok(database)
+    }
+    /// Create a database from a file using the given options.
+    /// In lenient mode, the problems found are returned alongside the
+    /// database instead of stopping the loading (see `ParseOptions`).
+    pub fn open_with_options(
+        filename: &str,
+        options: &ParseOptions,
+    ) -> Result<(Self, Vec<Diagnostic>), Error> {
+        let mut games: ItemCollection<Game> = ItemCollection::default();
+        let diagnostics = load_database(filename, options, &mut games)?;
+        Ok((Self::from_collection(games), diagnostics))
+    }
+    /// Create a database from any reader (stdin, network, ...).
+    /// Returns an error if the reader fails, is not valid UTF-8
+    /// or cannot be parsed.
+    /// ```
+    /// use pobsdlib::collections::DataBase;
+    /// let input = "Game\tToto\nTags\ttag1, tag2\nGame\tTiti\nTags\ttag1";
+    /// let db = DataBase::from_reader(input.as_bytes()).unwrap();
+    /// assert_eq!(db.get_games_count(), 2);
+    /// assert_eq!(db.get_tags_count(), 2);
+    /// ```
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, Error> {
+        let (database, _) = Self::from_reader_with_options(reader, &ParseOptions::default())?;
+        Ok(database)
+    }
+    /// Create a database from any reader using the given options
+    /// (see `DataBase::open_with_options`).
+    pub fn from_reader_with_options<R: Read>(
+        reader: R,
+        options: &ParseOptions,
+    ) -> Result<(Self, Vec<Diagnostic>), Error> {
         let mut games: ItemCollection<Game> = ItemCollection::default();
-        let mut tags: ItemCollection<Item> = ItemCollection::default();
-        let mut genres: ItemCollection<Item> = ItemCollection::default();
-        load_database(filename, &mut games);
-        load_tags_from_games(&mut tags, &games);
-        load_genres_from_games(&mut genres, &games);
+        let diagnostics =
+            load_database_from_reader(io::BufReader::new(reader), None, options, &mut games)?;
+        Ok((Self::from_collection(games), diagnostics))
+    }
+    /// Create a database from its JSON export.
+    /// The input is either an export (see `export::Export`), the whole
+    /// database or only its games collection (as written by older versions
+    /// of `database2json`). The games must have been exported. The tags and
+    /// genres are rebuilt from the games and, when present in the input,
+    /// checked against them.
+    /// Returns an error if the input is not valid JSON or is inconsistent.
+    /// ```
+    /// use pobsdlib::collections::DataBase;
+    /// let db: DataBase = "Game\tToto\nTags\ttag1, tag2".parse().unwrap();
+    /// let json = serde_json::to_string(&db).unwrap();
+    /// let db = DataBase::from_json(&json).unwrap();
+    /// assert_eq!(db.get_game_by_id(1).unwrap().name, "Toto");
+    /// assert_eq!(db.get_tags_count(), 2);
+    /// ```
+    pub fn from_json(input: &str) -> Result<Self, Error> {
+        Ok(serde_json::from_str(input)?)
+    }
+    /// Create a database from a vector of games.
+    /// The game ids are set according to their position in the vector.
+    /// ```
+    /// use pobsdlib::collections::DataBase;
+    /// use pobsdlib::models::Game;
+    /// let mut game = Game::new();
+    /// game.name = "Toto".to_string();
+    /// game.genres = vec!["RPG".to_string()];
+    /// let db = DataBase::from_games(vec![game]);
+    /// assert_eq!(db.get_game_by_id(1).unwrap().name, "Toto");
+    /// assert_eq!(db.get_games_by_genre("RPG").count, 1);
+    /// ```
+    pub fn from_games(games: Vec<Game>) -> Self {
+        let mut collection: ItemCollection<Game> = ItemCollection::default();
+        for game in games {
+            collection.add_item(game);
+        }
+        Self::from_collection(collection)
+    }
+    /// Build the other collections from the games collection.
+    fn from_collection(games: ItemCollection<Game>) -> Self {
+        let stable_ids = StableIds::new(&games);
         Self {
+            tags: ItemCollection::from_field(&games, "Tags"),
+            genres: ItemCollection::from_field(&games, "Genre"),
+            engines: ItemCollection::from_field(&games, "Engine"),
+            runtimes: ItemCollection::from_field(&games, "Runtime"),
+            devs: ItemCollection::from_field(&games, "Dev"),
+            publishers: ItemCollection::from_field(&games, "Pub"),
+            years: ItemCollection::from_field(&games, "Year"),
+            stores: ItemCollection::from_facet(&games, Facet::Store),
             games,
-            tags,
-            genres,
+            stable_ids,
+            completions: OnceLock::new(),
+        }
+    }
+    /// Write the database in the PlayOnBSD database format.
+    /// ```
+    /// use pobsdlib::collections::DataBase;
+    /// let input = "Game\tToto\nCover\nEngine\tFNA\nSetup\nRuntime\nStore\nHints\n\
+    ///              Genre\nTags\ttag1, tag2\nYear\nDev\nPub\nVersion\nStatus\n";
+    /// let db: DataBase = input.parse().unwrap();
+    /// let mut output: Vec<u8> = Vec::new();
+    /// db.write_to(&mut output).unwrap();
+    /// assert_eq!(String::from_utf8(output).unwrap(), input);
+    /// ```
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for game in &self.games.items {
+            write!(writer, "{}", game)?;
         }
+        writer.flush()
     }
     /// Return the number of games in the database
     pub fn get_games_count(&self) -> usize {
@@ -172,6 +737,117 @@ impl DataBase {
     pub fn get_game_by_id(&self, id: usize) -> Option<&Game> {
         self.games.get_item_by_id(id)
     }
+    /// Returns the stable id of the game corresponding to the id if it exists, None otherwise.
+    /// The stable id is derived from the name of the game (see `GameId`).
+    /// Games having the same name are told apart by their IGDB id, or else
+    /// by their year, so that their ids do not depend on the database order.
+    /// ```
+    /// use pobsdlib::collections::DataBase;
+    /// let db: DataBase = "Game\tToto\nYear\t2011\nGame\tTiti\nGame\tToto\nIgdbId\t12"
+    ///     .parse()
+    ///     .unwrap();
+    /// assert_eq!(db.get_game_stable_id(1).unwrap().as_str(), "toto--2011");
+    /// assert_eq!(db.get_game_stable_id(2).unwrap().as_str(), "titi");
+    /// assert_eq!(db.get_game_stable_id(3).unwrap().as_str(), "toto--igdb12");
+    /// ```
+    pub fn get_game_stable_id(&self, id: usize) -> Option<&GameId> {
+        self.stable_ids.ids.get(self.games.position_by_id(id)?)
+    }
+    /// Returns a reference to the game corresponding to the stable id if it exists, None otherwise.
+    /// ```
+    /// use pobsdlib::collections::DataBase;
+    /// use pobsdlib::models::GameId;
+    /// let db: DataBase = "Game\tToto\nGame\tTiti".parse().unwrap();
+    /// let id: GameId = "titi".parse().unwrap();
+    /// assert_eq!(db.get_game_by_stable_id(&id).unwrap().id, 2);
+    /// ```
+    pub fn get_game_by_stable_id(&self, id: &GameId) -> Option<&Game> {
+        self.get_game_by_id(*self.stable_ids.index.get(id)?)
+    }
+    /// Returns how the ids of the games changed between this database
+    /// and a newer version of it.
+    /// The games are matched by IGDB id when both versions have one,
+    /// by stable id otherwise.
+    /// ```
+    /// use pobsdlib::collections::DataBase;
+    /// let old: DataBase = "Game\tToto\nGame\tTiti".parse().unwrap();
+    /// let new: DataBase = "Game\tTata\nGame\tToto".parse().unwrap();
+    /// let migration = old.migrate_ids(&new);
+    /// assert_eq!(migration.get(1), Some(2));
+    /// assert_eq!(migration.removed, vec![2]);
+    /// assert_eq!(migration.added, vec![1]);
+    /// ```
+    pub fn migrate_ids(&self, newer: &DataBase) -> IdMigration {
+        let mut igdb_index: HashMap<&IgdbId, usize> = HashMap::new();
+        for game in &newer.games.items {
+            if let Some(igdb_id) = &game.igdb_id {
+                igdb_index.entry(igdb_id).or_insert(game.id);
+            }
+        }
+        let mut migration = IdMigration::default();
+        let mut used: HashSet<usize> = HashSet::new();
+        for (game, stable_id) in self.games.items.iter().zip(&self.stable_ids.ids) {
+            let by_igdb_id = game
+                .igdb_id
+                .as_ref()
+                .and_then(|igdb_id| igdb_index.get(igdb_id))
+                .filter(|id| !used.contains(*id));
+            let by_stable_id = newer
+                .stable_ids
+                .index
+                .get(stable_id)
+                .filter(|id| !used.contains(*id));
+            match by_igdb_id.or(by_stable_id) {
+                Some(&id) => {
+                    used.insert(id);
+                    migration.ids.insert(game.id, id);
+                }
+                None => migration.removed.push(game.id),
+            }
+        }
+        for game in &newer.games.items {
+            if !used.contains(&game.id) {
+                migration.added.push(game.id);
+            }
+        }
+        migration
+    }
+    /// Returns at most `limit` games whose name is similar to the given one,
+    /// the most similar first. The names are compared ignoring case,
+    /// punctuation and diacritics.
+    /// ```
+    /// use pobsdlib::collections::DataBase;
+    /// let db = DataBase::open("tests/data/test-games.db").unwrap();
+    /// let found = db.find_games_fuzzy("aaaaa for the awesome", 3);
+    /// assert_eq!(found[0].item.name, "AaaaaAAaaaAAAaaAAAAaAAAAA!!! for the Awesome");
+    /// ```
+    pub fn find_games_fuzzy(&self, name: &str, limit: usize) -> Vec<FuzzyMatch<'_, Game>> {
+        self.games.find_items_fuzzy(name, limit)
+    }
+    /// Returns the tag whose name is the most similar to the given one
+    /// ("did you mean"), None if no tag is similar enough.
+    /// ```
+    /// use pobsdlib::collections::DataBase;
+    /// let db: DataBase = "Game\tToto\nTags\tshoot 'em up, indie".parse().unwrap();
+    /// assert_eq!(db.suggest_tag("Shoot em up").unwrap().name, "shoot 'em up");
+    /// assert!(db.suggest_tag("strategy").is_none());
+    /// ```
+    pub fn suggest_tag(&self, name: &str) -> Option<&Item> {
+        Some(self.tags.find_items_fuzzy(name, 1).first()?.item)
+    }
+    /// Returns the genre whose name is the most similar to the given one
+    /// ("did you mean"), None if no genre is similar enough.
+    pub fn suggest_genre(&self, name: &str) -> Option<&Item> {
+        Some(self.genres.find_items_fuzzy(name, 1).first()?.item)
+    }
+    /// Returns at most `limit` values of the field starting with the prefix,
+    /// the ones used by the most games first (see `autocomplete`).
+    /// The index is built on the first call.
+    pub fn complete(&self, field: &str, prefix: &str, limit: usize) -> Vec<Completion<'_>> {
+        self.completions
+            .get_or_init(|| Completions::new(self))
+            .complete(field, prefix, limit)
+    }
     /// Returns a vector of references to games corresponding to the tag.
     pub fn get_games_by_tag(&self, name: &str) -> ItemCollection<&Game> {
         self.games.get_item_with_tag(name)
@@ -180,6 +856,101 @@ impl DataBase {
     pub fn get_games_by_genre(&self, name: &str) -> ItemCollection<&Game> {
         self.games.get_item_with_genre(name)
     }
+    /// Returns a vector of references to games corresponding to the engine.
+    /// The name must be exact, as for the other taxonomies below.
+    pub fn get_games_by_engine(&self, name: &str) -> ItemCollection<&Game> {
+        self.get_games_by_item(&self.engines, name)
+    }
+    /// Returns a vector of references to games corresponding to the runtime.
+    pub fn get_games_by_runtime(&self, name: &str) -> ItemCollection<&Game> {
+        self.get_games_by_item(&self.runtimes, name)
+    }
+    /// Returns a vector of references to games corresponding to the developer.
+    pub fn get_games_by_dev(&self, name: &str) -> ItemCollection<&Game> {
+        self.get_games_by_item(&self.devs, name)
+    }
+    /// Returns a vector of references to games corresponding to the publisher.
+    pub fn get_games_by_publisher(&self, name: &str) -> ItemCollection<&Game> {
+        self.get_games_by_item(&self.publishers, name)
+    }
+    /// Returns a vector of references to games corresponding to the release year.
+    pub fn get_games_by_year(&self, year: &str) -> ItemCollection<&Game> {
+        self.get_games_by_item(&self.years, year)
+    }
+    /// Returns a vector of references to games sold on the store host
+    /// (e.g. "gog.com").
+    /// ```
+    /// use pobsdlib::collections::DataBase;
+    /// let db = DataBase::open("tests/data/test-games.db").unwrap();
+    /// assert_eq!(db.get_games_by_store("gog.com").count, 3);
+    /// assert_eq!(db.get_games_by_engine("FNA").count, 2);
+    /// assert_eq!(db.get_games_by_year("2014").count, 3);
+    /// ```
+    pub fn get_games_by_store(&self, host: &str) -> ItemCollection<&Game> {
+        self.get_games_by_item(&self.stores, host)
+    }
+    /// Returns the games of the item of the collection having the name.
+    fn get_games_by_item(&self, items: &ItemCollection<Item>, name: &str) -> ItemCollection<&Game> {
+        match items.get_item_by_name(name) {
+            Some(item) => self.games_of(item),
+            None => ItemCollection::new(Vec::new()),
+        }
+    }
+    /// Returns the games of a tag, genre, engine... in the database order.
+    /// Ids not corresponding to a game are ignored.
+    /// ```
+    /// use pobsdlib::collections::DataBase;
+    /// let db: DataBase = "Game\tToto\nTags\tindie\nGame\tTiti\nTags\tindie".parse().unwrap();
+    /// let indie = db.tags.get_item_by_name("indie").unwrap();
+    /// assert_eq!(db.games_of(indie).items[1].name, "Titi");
+    /// ```
+    pub fn games_of(&self, item: &Item) -> ItemCollection<&Game> {
+        let mut games: Vec<&Game> = Vec::new();
+        for id in &item.games {
+            if let Some(game) = self.get_game_by_id(*id) {
+                games.push(game);
+            }
+        }
+        ItemCollection::new(games)
+    }
+    /// Returns the tags of the game, in the order of the game.
+    /// ```
+    /// use pobsdlib::collections::DataBase;
+    /// let db: DataBase = "Game\tToto\nTags\tindie, rpg\nGenre\tRPG".parse().unwrap();
+    /// let game = db.get_game_by_id(1).unwrap();
+    /// let tags: Vec<&str> = db.tags_of(game).iter().map(|tag| tag.name.as_str()).collect();
+    /// assert_eq!(tags, vec!["indie", "rpg"]);
+    /// assert_eq!(db.genres_of(game)[0].games, vec![1]);
+    /// ```
+    pub fn tags_of(&self, game: &Game) -> Vec<&Item> {
+        Self::items_of(&self.tags, &game.tags)
+    }
+    /// Returns the genres of the game, in the order of the game.
+    pub fn genres_of(&self, game: &Game) -> Vec<&Item> {
+        Self::items_of(&self.genres, &game.genres)
+    }
+    /// Returns the items having the names, each item being given once.
+    fn items_of<'a>(items: &'a ItemCollection<Item>, names: &[String]) -> Vec<&'a Item> {
+        let mut found: Vec<&Item> = Vec::new();
+        for name in names {
+            if let Some(item) = items.get_item_by_name(name.trim()) {
+                if !found.iter().any(|other| other.id == item.id) {
+                    found.push(item);
+                }
+            }
+        }
+        found
+    }
+    /// Returns the games containing the words of the text, the best ones
+    /// first (see `search`). The games are indexed at each call, use a
+    /// `search::SearchIndex` to run several searches.
+    pub fn search_games(&self, text: &str) -> Vec<SearchResult<'_, Game>> {
+        SearchIndex::new(&self.games).search(text)
+    }
+    /// Returns a vector of references to games matching the query (see `query`).
+    pub fn get_games_by_query(&self, query: &Query) -> ItemCollection<&Game> {
+        self.games.get_item_with_query(query)
+    }
     /// Return the number of tags in the database
     pub fn get_tags_count(&self) -> usize {
         self.tags.count
@@ -196,6 +967,609 @@ impl DataBase {
     pub fn get_genres_count(&self) -> usize {
         self.genres.count
     }
+    /// Return the genres in the database
+    pub fn get_genre_names(&self) -> ItemCollection<&str> {
+        let mut genres: Vec<&str> = Vec::new();
+        for genre in &self.genres.items {
+            genres.push(genre.name.as_str());
+        }
+        ItemCollection::new(genres)
+    }
+}
+
+/// Mutation API
+/// The games are changed through the database so that the other
+/// collections, the name index and the stable ids stay consistent.
+/// Each call returns a description of the change (see `Change`).
+impl DataBase {
+    /// Adds the game to the database and returns the change, giving the
+    /// id of the game. The id of the game given is ignored.
+    /// ```
+    /// use pobsdlib::collections::DataBase;
+    /// use pobsdlib::models::Game;
+    /// let mut db: DataBase = "Game\tToto\nTags\tindie".parse().unwrap();
+    /// let mut game = Game::new();
+    /// game.name = "Titi".to_string();
+    /// game.tags = vec!["indie".to_string(), "rpg".to_string()];
+    /// let change = db.insert_game(game);
+    /// assert_eq!(change.id, 2);
+    /// assert_eq!(change.created, vec![("Tags", "rpg".to_string())]);
+    /// assert_eq!(db.get_games_by_tag("rpg").count, 1);
+    /// assert_eq!(db.tags.get_item_by_name("indie").unwrap().games, vec![1, 2]);
+    /// ```
+    pub fn insert_game(&mut self, game: Game) -> Change {
+        let mut change = Change::new(ChangeKind::Inserted, &game);
+        change.fields = changed_fields(None, Some(&game));
+        let values = taxonomy_values(&game);
+        change.id = self.games.add_item(game);
+        self.link_game(change.id, values, &mut change);
+        self.stable_ids
+            .insert(&self.games, self.games.items.len() - 1);
+        self.completions = OnceLock::new();
+        change
+    }
+    /// Changes the game corresponding to the id and returns the change,
+    /// None if there is no such game. The id of the game cannot be changed.
+    /// ```
+    /// use pobsdlib::collections::DataBase;
+    /// let mut db: DataBase = "Game\tToto\nTags\tindie\nEngine\tFNA".parse().unwrap();
+    /// let change = db.update_game(1, |game| game.tags = vec!["rpg".to_string()]).unwrap();
+    /// assert_eq!(change.fields, vec!["Tags"]);
+    /// assert_eq!(change.removed, vec![("Tags", "indie".to_string())]);
+    /// assert!(db.get_games_by_tag("indie").items.is_empty());
+    /// assert_eq!(db.get_games_by_tag("rpg").count, 1);
+    /// ```
+    pub fn update_game<F: FnOnce(&mut Game)>(&mut self, id: usize, update: F) -> Option<Change> {
+        let position = self.games.position_by_id(id)?;
+        let previous = GameId::from_name(&self.games.items[position].name);
+        let change = self.apply_update(position, update);
+        // the stable ids only depend on these fields
+        if change
+            .fields
+            .iter()
+            .any(|field| ["Game", "Year", "IgdbId"].contains(&field.as_str()))
+        {
+            self.stable_ids.update(&self.games, position, &previous);
+        }
+        self.completions = OnceLock::new();
+        Some(change)
+    }
+    /// Changes the game at the position and its taxonomies, but not the
+    /// stable ids nor the completions.
+    fn apply_update<F: FnOnce(&mut Game)>(&mut self, position: usize, update: F) -> Change {
+        let before = self.games.items[position].clone();
+        {
+            // the guard updates the name index
+            let mut game = ItemMut::new(&mut self.games, position);
+            update(&mut game);
+            game.id = before.id;
+        }
+        let game = &self.games.items[position];
+        let mut change = Change::new(ChangeKind::Updated, game);
+        change.fields = changed_fields(Some(&before), Some(game));
+        let mut removed = taxonomy_values(&before);
+        let mut added = taxonomy_values(game);
+        // only the values that changed are unlinked and linked
+        for (old, new) in removed.iter_mut().zip(added.iter_mut()) {
+            old.retain(|value| match new.iter().position(|other| other == value) {
+                Some(index) => {
+                    new.remove(index);
+                    false
+                }
+                None => true,
+            });
+        }
+        self.unlink_game(before.id, removed, &mut change);
+        self.link_game(before.id, added, &mut change);
+        change
+    }
+    /// Renames the game corresponding to the id and returns the change,
+    /// None if there is no such game.
+    /// ```
+    /// use pobsdlib::collections::DataBase;
+    /// let mut db: DataBase = "Game\tToto".parse().unwrap();
+    /// db.rename_game(1, "Titi").unwrap();
+    /// assert_eq!(db.get_game_by_name("Titi").unwrap().id, 1);
+    /// assert!(db.get_game_by_name("Toto").is_none());
+    /// assert_eq!(db.get_game_stable_id(1).unwrap().as_str(), "titi");
+    /// ```
+    pub fn rename_game(&mut self, id: usize, name: &str) -> Option<Change> {
+        self.update_game(id, |game| game.name = name.to_string())
+    }
+    /// Removes the game corresponding to the id and returns the change,
+    /// None if there is no such game. The ids of the other games do not change.
+    /// ```
+    /// use pobsdlib::collections::DataBase;
+    /// let mut db: DataBase = "Game\tToto\nTags\tindie\nGame\tTiti".parse().unwrap();
+    /// let change = db.remove_game(1).unwrap();
+    /// assert_eq!(change.name, "Toto");
+    /// assert_eq!(db.get_tags_count(), 0);
+    /// assert_eq!(db.get_game_by_id(2).unwrap().name, "Titi");
+    /// ```
+    pub fn remove_game(&mut self, id: usize) -> Option<Change> {
+        let game = self.games.remove_item(id)?;
+        let mut change = Change::new(ChangeKind::Removed, &game);
+        change.fields = changed_fields(Some(&game), None);
+        self.unlink_game(id, taxonomy_values(&game), &mut change);
+        self.refresh();
+        Some(change)
+    }
+    /// Renames the tag in all the games and returns the games changed.
+    /// If the new name is already a tag, both tags are merged.
+    /// ```
+    /// use pobsdlib::collections::DataBase;
+    /// let mut db: DataBase = "Game\tToto\nTags\tshmup, indie\nGame\tTiti".parse().unwrap();
+    /// let changes = db.rename_tag("shmup", "shoot 'em up");
+    /// assert_eq!(changes[0].name, "Toto");
+    /// assert_eq!(db.get_game_by_id(1).unwrap().tags, vec!["shoot 'em up", "indie"]);
+    /// assert!(db.tags.get_item_by_name("shmup").is_none());
+    /// ```
+    pub fn rename_tag(&mut self, from: &str, into: &str) -> Vec<Change> {
+        self.merge_tags(&[from], into)
+    }
+    /// Replaces the tags by a single one in all the games and returns the
+    /// games changed. A game having several of the tags gets the new tag once.
+    /// ```
+    /// use pobsdlib::collections::DataBase;
+    /// let mut db: DataBase = "Game\tToto\nTags\tshmup, shoot em up".parse().unwrap();
+    /// let changes = db.merge_tags(&["shmup", "shoot em up"], "shoot 'em up");
+    /// assert_eq!(changes[0].removed.len(), 2);
+    /// assert_eq!(db.get_game_by_id(1).unwrap().tags, vec!["shoot 'em up"]);
+    /// ```
+    pub fn merge_tags(&mut self, from: &[&str], into: &str) -> Vec<Change> {
+        self.replace_values(|db| &db.tags, |game| &mut game.tags, from, &[into])
+    }
+    /// Replaces the tag by several ones in all the games and returns the games changed.
+    pub fn split_tag(&mut self, from: &str, into: &[&str]) -> Vec<Change> {
+        self.replace_values(|db| &db.tags, |game| &mut game.tags, &[from], into)
+    }
+    /// Renames the genre in all the games and returns the games changed.
+    /// If the new name is already a genre, both genres are merged.
+    pub fn rename_genre(&mut self, from: &str, into: &str) -> Vec<Change> {
+        self.merge_genres(&[from], into)
+    }
+    /// Replaces the genres by a single one in all the games and returns
+    /// the games changed.
+    pub fn merge_genres(&mut self, from: &[&str], into: &str) -> Vec<Change> {
+        self.replace_values(|db| &db.genres, |game| &mut game.genres, from, &[into])
+    }
+    /// Replaces the genre by several ones in all the games and returns the
+    /// games changed. The new genres take the place of the old one.
+    /// ```
+    /// use pobsdlib::collections::DataBase;
+    /// let mut db: DataBase = "Game\tToto\nGenre\tPuzzle Platformer, RPG".parse().unwrap();
+    /// db.split_genre("Puzzle Platformer", &["Puzzle", "Platformer"]);
+    /// assert_eq!(db.get_game_by_id(1).unwrap().genres, vec!["Puzzle", "Platformer", "RPG"]);
+    /// assert_eq!(db.get_games_by_genre("Puzzle").count, 1);
+    /// ```
+    pub fn split_genre(&mut self, from: &str, into: &[&str]) -> Vec<Change> {
+        self.replace_values(|db| &db.genres, |game| &mut game.genres, &[from], into)
+    }
+    /// Replaces the values `from` by the values `into` in the games having
+    /// one of them, each value being kept once.
+    fn replace_values(
+        &mut self,
+        items: fn(&DataBase) -> &ItemCollection<Item>,
+        values: fn(&mut Game) -> &mut Vec<String>,
+        from: &[&str],
+        into: &[&str],
+    ) -> Vec<Change> {
+        let mut ids: Vec<usize> = Vec::new();
+        for name in from {
+            if let Some(item) = items(self).get_item_by_name(name) {
+                ids.extend(&item.games);
+            }
+        }
+        ids.sort_unstable();
+        ids.dedup();
+        let into: Vec<&str> = into
+            .iter()
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+            .collect();
+        let mut changes: Vec<Change> = Vec::new();
+        for id in ids {
+            let position = match self.games.position_by_id(id) {
+                Some(position) => position,
+                None => continue,
+            };
+            let change = self.apply_update(position, |game| {
+                let old = std::mem::take(values(game));
+                let mut new: Vec<String> = Vec::new();
+                for value in old {
+                    let replaced: Vec<&str> = if from.contains(&value.trim()) {
+                        into.clone()
+                    } else {
+                        vec![value.as_str()]
+                    };
+                    for value in replaced {
+                        if !new.iter().any(|other| other.trim() == value.trim()) {
+                            new.push(value.to_string());
+                        }
+                    }
+                }
+                *values(game) = new;
+            });
+            if !change.fields.is_empty() {
+                changes.push(change);
+            }
+        }
+        // the stable ids and the completions are rebuilt once for all the games
+        if !changes.is_empty() {
+            self.refresh();
+        }
+        changes
+    }
+    /// Returns the taxonomies of the database, in the order of TAXONOMIES.
+    fn taxonomies_mut(&mut self) -> [&mut ItemCollection<Item>; 8] {
+        [
+            &mut self.tags,
+            &mut self.genres,
+            &mut self.engines,
+            &mut self.runtimes,
+            &mut self.devs,
+            &mut self.publishers,
+            &mut self.years,
+            &mut self.stores,
+        ]
+    }
+    /// Adds the game id to the items of the values, creating the missing items.
+    fn link_game(&mut self, id: usize, values: Vec<Vec<String>>, change: &mut Change) {
+        for ((facet, items), values) in TAXONOMIES.iter().zip(self.taxonomies_mut()).zip(values) {
+            for value in values {
+                if let Some(mut item) = items.get_item_by_name_mut(&value) {
+                    // keep the ids in the database order
+                    let index = item.games.partition_point(|other| *other <= id);
+                    item.games.insert(index, id);
+                    continue;
+                }
+                let mut item = Item::new();
+                item.name = value.clone();
+                item.games.push(id);
+                items.add_item(item);
+                change.created.push((facet.field_name(), value));
+            }
+        }
+    }
+    /// Removes the game id from the items of the values, removing the
+    /// items left without game.
+    fn unlink_game(&mut self, id: usize, values: Vec<Vec<String>>, change: &mut Change) {
+        for ((facet, items), values) in TAXONOMIES.iter().zip(self.taxonomies_mut()).zip(values) {
+            for value in values {
+                let item_id = match items.get_item_by_name_mut(&value) {
+                    Some(mut item) => {
+                        if let Some(index) = item.games.iter().position(|other| *other == id) {
+                            item.games.remove(index);
+                        }
+                        item.games.is_empty().then_some(item.id)
+                    }
+                    None => continue,
+                };
+                if let Some(item_id) = item_id {
+                    items.remove_item(item_id);
+                    change.removed.push((facet.field_name(), value));
+                }
+            }
+        }
+    }
+    /// Rebuilds the stable ids and forgets the completions once the games changed.
+    fn refresh(&mut self) {
+        self.stable_ids = StableIds::new(&self.games);
+        self.completions = OnceLock::new();
+    }
+}
+
+/// The facets giving the values of the taxonomies of the database.
+const TAXONOMIES: [Facet; 8] = [
+    Facet::Tags,
+    Facet::Genre,
+    Facet::Engine,
+    Facet::Runtime,
+    Facet::Dev,
+    Facet::Pub,
+    Facet::Year,
+    Facet::Store,
+];
+
+/// Returns the values of the game for each taxonomy, in the order of TAXONOMIES.
+/// A value given twice is returned once, as the game is linked to it once.
+fn taxonomy_values(game: &Game) -> Vec<Vec<String>> {
+    TAXONOMIES
+        .iter()
+        .map(|facet| {
+            let mut values: Vec<String> = Vec::new();
+            for value in facet.values(game) {
+                if !values.contains(&value) {
+                    values.push(value);
+                }
+            }
+            values
+        })
+        .collect()
+}
+
+/// Returns the names of the fields whose values differ, in the database
+/// order followed by the extra fields. A missing game has no value.
+fn changed_fields(before: Option<&Game>, after: Option<&Game>) -> Vec<String> {
+    let values = |game: Option<&Game>, name: &str| -> Vec<String> {
+        match game.map(|game| game.get_field(name)) {
+            Some(Ok(field)) => field
+                .values()
+                .into_iter()
+                .filter(|value| !value.is_empty())
+                .map(|value| value.to_string())
+                .collect(),
+            _ => Vec::new(),
+        }
+    };
+    let mut names: Vec<&str> = FIELD_NAMES.to_vec();
+    for game in before.iter().chain(after.iter()) {
+        for (name, _) in game.extra.iter() {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    names
+        .into_iter()
+        .filter(|name| values(before, name) != values(after, name))
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/* ------------------------ CHANGES -------------------------*/
+/// # The kind of change made to a game
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ChangeKind {
+    Inserted,
+    Updated,
+    Removed,
+}
+
+/// # Represent a change made to a game of the database
+/// See the mutation API of `DataBase`.
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct Change {
+    pub kind: ChangeKind,
+    /// The id of the game.
+    pub id: usize,
+    /// The name of the game, after the change for an updated game.
+    pub name: String,
+    /// The fields whose value changed, all the fields having a value for
+    /// an inserted or removed game.
+    pub fields: Vec<String>,
+    /// The items created in the collections (tags, genres, engines...),
+    /// as the name of the field and the value.
+    pub created: Vec<(&'static str, String)>,
+    /// The items removed from the collections as no game has their value anymore.
+    pub removed: Vec<(&'static str, String)>,
+}
+
+impl Change {
+    fn new(kind: ChangeKind, game: &Game) -> Self {
+        Self {
+            kind,
+            id: game.id,
+            name: game.name.clone(),
+            fields: Vec::new(),
+            created: Vec::new(),
+            removed: Vec::new(),
+        }
+    }
+}
+
+/// # Represent how the game ids changed between two versions of the database
+/// See `DataBase::migrate_ids`.
+#[derive(Default, PartialEq, Debug)]
+pub struct IdMigration {
+    /// The new id of the games present in both versions, by old id.
+    pub ids: BTreeMap<usize, usize>,
+    /// The ids of the games only present in the older version.
+    pub removed: Vec<usize>,
+    /// The ids of the games only present in the newer version.
+    pub added: Vec<usize>,
+}
+
+impl IdMigration {
+    /// Returns the new id of the game if it is still in the database, None otherwise.
+    pub fn get(&self, old_id: usize) -> Option<usize> {
+        self.ids.get(&old_id).cloned()
+    }
+}
+
+/// The fields of a JSON export, being either an export, a whole database
+/// or only its games collection.
+#[derive(Deserialize)]
+struct DataBaseFields {
+    schema_version: Option<u32>,
+    game_count: Option<usize>,
+    games: Option<ItemCollection<Game>>,
+    tags: Option<ItemCollection<Item>>,
+    genres: Option<ItemCollection<Item>>,
+    count: Option<usize>,
+    items: Option<Vec<Game>>,
+}
+
+/// Checks that the count is right and that the ids are unique.
+fn check_collection<T: ItemTraits>(
+    name: &str,
+    collection: &ItemCollection<T>,
+) -> Result<(), String> {
+    if collection.count != collection.items.len() {
+        return Err(format!(
+            "{} count is {} but there are {} {}",
+            name,
+            collection.count,
+            collection.items.len(),
+            name
+        ));
+    }
+    let mut ids: HashSet<usize> = HashSet::new();
+    for item in &collection.items {
+        if item.get_id() == 0 || !ids.insert(item.get_id()) {
+            return Err(format!(
+                "{} {} has id {}, which is zero or already used",
+                name,
+                item.get_name(),
+                item.get_id()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the items read match the ones rebuilt from the games.
+fn check_items(
+    name: &str,
+    read: &ItemCollection<Item>,
+    rebuilt: &ItemCollection<Item>,
+) -> Result<(), String> {
+    check_collection(name, read)?;
+    for item in &read.items {
+        match rebuilt.get_item_by_name(&item.name) {
+            Some(other) if other.games == item.games => (),
+            Some(_) => {
+                return Err(format!(
+                    "{} {} does not reference the games having it",
+                    name, item.name
+                ))
+            }
+            None => return Err(format!("{} {} is not used by any game", name, item.name)),
+        }
+    }
+    if read.count != rebuilt.count {
+        return Err(format!("some {} are missing", name));
+    }
+    Ok(())
+}
+
+impl<'de> Deserialize<'de> for DataBase {
+    /// Deserializes a database or a games collection (see `DataBase::from_json`).
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fields = DataBaseFields::deserialize(deserializer)?;
+        if let Some(version) = fields.schema_version {
+            if version > SCHEMA_VERSION {
+                return Err(de::Error::custom(format!(
+                    "schema version {} is not supported (up to {})",
+                    version, SCHEMA_VERSION
+                )));
+            }
+        }
+        let mut games = match (fields.games, fields.items) {
+            (Some(games), _) => games,
+            (None, Some(items)) => {
+                let mut games = ItemCollection::new(items);
+                games.count = fields.count.unwrap_or(games.count);
+                games
+            }
+            (None, None) => return Err(de::Error::missing_field("games")),
+        };
+        check_collection("games", &games).map_err(de::Error::custom)?;
+        games.reindex();
+        if let Some(count) = fields.game_count {
+            if count != games.count {
+                return Err(de::Error::custom(format!(
+                    "game count is {} but there are {} games",
+                    count, games.count
+                )));
+            }
+        }
+        let database = Self::from_collection(games);
+        if let Some(tags) = fields.tags {
+            check_items("tags", &tags, &database.tags).map_err(de::Error::custom)?;
+        }
+        if let Some(genres) = fields.genres {
+            check_items("genres", &genres, &database.genres).map_err(de::Error::custom)?;
+        }
+        Ok(database)
+    }
+}
+
+/// # DataBaseBuffer
+/// Own the text of the database so that games can be parsed without
+/// copying their values: the games are GameRef borrowing their values
+/// from the buffer. This is useful when the database is loaded often and
+/// only read.
+///
+/// Unlike what was first planned, the buffer is not owned by a DataBase:
+/// a DataBase holding both the text and games borrowing from it would be
+/// self-referential. The buffer lends an ItemCollection of GameRef
+/// instead, which offers the lookups, queries and filters of the
+/// collections but neither the taxonomies nor the mutations of DataBase.
+/// ```
+/// use pobsdlib::collections::DataBaseBuffer;
+/// let buffer = DataBaseBuffer::open("tests/data/test-games.db").unwrap();
+/// let games = buffer.games().unwrap();
+/// assert_eq!(games.count, 8);
+/// let game = games.get_item_by_name("Akane the Kunoichi").unwrap();
+/// assert_eq!(game.engine, "XNA");
+/// assert_eq!(games.get_item_with_tag("indie").count, 2);
+/// ```
+pub struct DataBaseBuffer {
+    text: String,
+    file: Option<String>,
+}
+
+impl DataBaseBuffer {
+    /// Create a buffer from a text.
+    pub fn new(text: String) -> Self {
+        Self { text, file: None }
+    }
+    /// Create a buffer from a file.
+    /// Returns an error if the file cannot be read or is not valid UTF-8.
+    pub fn open(filename: &str) -> Result<Self, Error> {
+        let bytes = fs::read(filename)?;
+        let text = decode_text(bytes).map_err(|error| error.with_file(filename))?;
+        Ok(Self {
+            text,
+            file: Some(filename.to_string()),
+        })
+    }
+    /// Create a buffer from any reader.
+    /// Returns an error if the reader fails or is not valid UTF-8.
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self, Error> {
+        let mut bytes: Vec<u8> = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Ok(Self::new(decode_text(bytes)?))
+    }
+    /// Returns the text of the database.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+    /// Parses the games using the default options.
+    pub fn games(&self) -> Result<ItemCollection<GameRef<'_>>, Error> {
+        let (games, _) = self.games_with_options(&ParseOptions::default())?;
+        Ok(games)
+    }
+    /// Parses the games using the given options (see `DataBase::open_with_options`).
+    pub fn games_with_options(
+        &self,
+        options: &ParseOptions,
+    ) -> Result<(ItemCollection<GameRef<'_>>, Vec<Diagnostic>), Error> {
+        let mut reader = GameRefReader::with_options(&self.text, options.clone());
+        if let Some(file) = &self.file {
+            reader = reader.with_file(file);
+        }
+        let mut games: ItemCollection<GameRef> = ItemCollection::default();
+        for game in &mut reader {
+            games.add_item(game?);
+        }
+        Ok((games, reader.into_diagnostics()))
+    }
+}
+
+impl FromStr for DataBase {
+    type Err = Error;
+    /// Create a database from a string.
+    /// ```
+    /// use pobsdlib::collections::DataBase;
+    /// let db: DataBase = "Game\tToto\nGenre\tRPG".parse().unwrap();
+    /// assert_eq!(db.get_games_count(), 1);
+    /// assert_eq!(db.get_genres_count(), 1);
+    /// ```
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Self::from_reader(input.as_bytes())
+    }
 }
 
 /*-------------------------- TESTS --------------------------------*/
@@ -238,6 +1612,21 @@ mod test_collection_items_methods {
         }
     }
     #[test]
+    fn find_fuzzy() {
+        let mut collection: ItemCollection<Item> = ItemCollection::default();
+        for name in &["Pokémon Snap", "Shuggy", "Pokemon"] {
+            let mut item = Item::new();
+            item.name = name.to_string();
+            collection.add_item(item);
+        }
+        let found = collection.find_items_fuzzy("POKEMON", 5);
+        let names: Vec<&str> = found.iter().map(|f| f.item.name.as_str()).collect();
+        assert_eq!(names, vec!["Pokemon", "Pokémon Snap"]);
+        assert_eq!(found[0].similarity, 1.0);
+        assert_eq!(collection.find_items_fuzzy("pokemon", 1).len(), 1);
+        assert!(collection.find_items_fuzzy("", 5).is_empty());
+    }
+    #[test]
     fn get_by_id() {
         let mut item1 = Item::new();
         item1.id = 1;
@@ -254,6 +1643,130 @@ mod test_collection_items_methods {
     }
 }
 
+#[cfg(test)]
+mod test_collection_ids {
+    use super::*;
+    use models::{Game, Item};
+    fn item(id: usize) -> Item {
+        let mut item = Item::new();
+        item.id = id;
+        item.name = format!("item {}", id);
+        item
+    }
+    #[test]
+    fn id_zero() {
+        let mut collection = ItemCollection::new(vec![item(1)]);
+        assert!(collection.get_item_by_id(0).is_none());
+        assert!(collection.get_item_by_id_mut(0).is_none());
+        collection.reindex();
+        assert!(collection.get_item_by_id(0).is_none());
+        let empty: ItemCollection<Item> = ItemCollection::default();
+        assert!(empty.get_item_by_id(0).is_none());
+    }
+    #[test]
+    fn sparse_ids() {
+        let mut collection = ItemCollection::new(vec![item(3), item(7), item(42)]);
+        assert_eq!(collection.get_item_by_id(7).unwrap().name, "item 7");
+        assert!(collection.get_item_by_id(2).is_none());
+        collection.reindex();
+        assert_eq!(collection.get_item_by_id(42).unwrap().name, "item 42");
+        assert!(collection.get_item_by_id(1).is_none());
+        assert_eq!(collection.add_item(Item::new()), 43);
+    }
+    #[test]
+    fn remove_item() {
+        let mut collection: ItemCollection<Item> = ItemCollection::default();
+        for _ in 0..3 {
+            collection.add_item(Item::new());
+        }
+        assert_eq!(collection.remove_item(2).unwrap().id, 2);
+        assert!(collection.remove_item(2).is_none());
+        assert_eq!(collection.count, 2);
+        assert_eq!(collection.get_item_by_id(3).unwrap().id, 3);
+        assert_eq!(collection.add_item(Item::new()), 4);
+        assert_eq!(collection.get_item_by_id_mut(4).unwrap().id, 4);
+    }
+    #[test]
+    fn filtered_subset() {
+        let mut collection: ItemCollection<Game> = ItemCollection::default();
+        for tag in &["tag1", "tag2", "tag1"] {
+            let mut game = Game::new();
+            game.tags = vec![tag.to_string()];
+            collection.add_item(game);
+        }
+        let subset = collection.get_item_with_tag("tag1");
+        assert_eq!(subset.get_item_by_id(3).unwrap().id, 3);
+        assert!(subset.get_item_by_id(2).is_none());
+    }
+}
+
+#[cfg(test)]
+mod test_collection_name_index {
+    use super::*;
+    use models::Item;
+    fn item(name: &str) -> Item {
+        let mut item = Item::new();
+        item.name = name.to_string();
+        item
+    }
+    #[test]
+    fn add_item_updates_index() {
+        let mut collection: ItemCollection<Item> = ItemCollection::default();
+        collection.add_item(item("item 1"));
+        collection.add_item(item("item 2"));
+        collection.add_item(item("item 2"));
+        assert_eq!(collection.names.len(), 2);
+        assert_eq!(collection.get_item_by_name("item 2").unwrap().id, 2);
+        assert!(collection.get_item_by_name("Item 2").is_none());
+    }
+    #[test]
+    fn ignore_case() {
+        let mut collection: ItemCollection<Item> =
+            ItemCollection::new(vec![item("Écran")]).with_name_matching(NameMatching::IgnoreCase);
+        collection.add_item(item("Indie"));
+        assert_eq!(collection.get_item_by_name("éCRAN").unwrap().name, "Écran");
+        assert_eq!(collection.get_item_by_name("INDIE").unwrap().name, "Indie");
+    }
+    #[test]
+    fn direct_changes() {
+        let mut collection: ItemCollection<Item> = ItemCollection::default();
+        collection.add_item(item("item 1"));
+        collection.items.push(item("item 2"));
+        assert_eq!(
+            collection.get_item_by_name("item 2").unwrap().name,
+            "item 2"
+        );
+        collection.add_item(item("item 3"));
+        assert_eq!(
+            collection.get_item_by_name("item 3").unwrap().name,
+            "item 3"
+        );
+        collection.reindex();
+        collection.items[0].name = "item 0".to_string();
+        assert!(collection.get_item_by_name("item 1").is_none());
+        collection.reindex();
+        assert_eq!(collection.get_item_by_name("item 0").unwrap().id, 1);
+    }
+    #[test]
+    fn rename_through_mut() {
+        let mut collection: ItemCollection<Item> = ItemCollection::default();
+        collection.add_item(item("item 1"));
+        collection.add_item(item("item 2"));
+        collection.get_item_by_name_mut("item 1").unwrap().name = "item 0".to_string();
+        assert!(collection.get_item_by_name("item 1").is_none());
+        assert_eq!(collection.get_item_by_name("item 0").unwrap().id, 1);
+        // the second item with a name is found once the first one is renamed
+        collection.get_item_by_id_mut(2).unwrap().name = "item 0".to_string();
+        collection.get_item_by_id_mut(1).unwrap().name = "item 1".to_string();
+        assert_eq!(collection.get_item_by_name("item 0").unwrap().id, 2);
+        assert_eq!(collection.get_item_by_name("item 1").unwrap().id, 1);
+        collection.get_item_by_id_mut(2).unwrap().id = 5;
+        assert!(collection.get_item_by_id(2).is_none());
+        assert_eq!(collection.get_item_by_id(5).unwrap().name, "item 0");
+        assert_eq!(collection.add_item(item("item 3")), 6);
+    }
+}
+
 #[cfg(test)]
 mod test_collection_games_methods {
     use super::*;
@@ -290,4 +1803,160 @@ mod test_collection_games_methods {
         assert_eq!(g1_test.items[0].name, "to be found".to_string());
         assert_eq!(g1_test.count, 1);
     }
+    #[test]
+    fn taxonomies() {
+        let mut games: Vec<Game> = Vec::new();
+        for (dev, stores) in &[
+            ("Dev 1", "https://www.gog.com/a https://gog.com/b"),
+            (" ", "https://zarkonnen.itch.io/"),
+            ("Dev 1", ""),
+        ] {
+            let mut game = Game::new();
+            game.dev = dev.to_string();
+            game.store = stores.split_whitespace().map(|s| s.to_string()).collect();
+            games.push(game);
+        }
+        let db = DataBase::from_games(games);
+        assert_eq!(db.devs.count, 1);
+        assert_eq!(db.devs.items[0].games, vec![1, 3]);
+        assert_eq!(db.get_games_by_dev("Dev 1").count, 2);
+        assert_eq!(
+            db.stores.get_item_by_name("gog.com").unwrap().games,
+            vec![1]
+        );
+        assert_eq!(db.get_games_by_store("gog.com").count, 1);
+        assert_eq!(db.get_games_by_store("zarkonnen.itch.io").items[0].id, 2);
+        assert!(db.get_games_by_store("steam").items.is_empty());
+        let unknown = ItemCollection::from_field(&db.games, "Unknown");
+        assert_eq!(unknown.count, 0);
+    }
+    #[test]
+    fn name_counts() {
+        let db: DataBase = "Game\tToto\nStore\thttps://www.gog.com/a https://gog.com/b\n\
+                            Game\tTiti\nStore\thttps://zarkonnen.itch.io/"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            db.stores.get_name_counts(),
+            vec![("gog.com", 1), ("zarkonnen.itch.io", 1)]
+        );
+        let gog = db.stores.get_item_by_name("gog.com").unwrap();
+        assert_eq!(db.games_of(gog).count, 1);
+    }
+}
+
+#[cfg(test)]
+mod test_database_mutations {
+    use super::*;
+    /// Checks that the collections are the ones built from the games.
+    fn check_consistency(db: &mut DataBase) {
+        let games = std::mem::take(&mut db.games.items);
+        let rebuilt = DataBase::from_collection(ItemCollection::new(games));
+        let names = |items: &ItemCollection<Item>| {
+            let mut names: Vec<(String, Vec<usize>)> = items
+                .items
+                .iter()
+                .map(|item| (item.name.clone(), item.games.clone()))
+                .collect();
+            names.sort();
+            names
+        };
+        for (items, expected) in db.taxonomies_mut().iter().zip([
+            &rebuilt.tags,
+            &rebuilt.genres,
+            &rebuilt.engines,
+            &rebuilt.runtimes,
+            &rebuilt.devs,
+            &rebuilt.publishers,
+            &rebuilt.years,
+            &rebuilt.stores,
+        ]) {
+            assert_eq!(names(items), names(expected));
+            assert_eq!(items.count, items.items.len());
+        }
+        assert_eq!(db.stable_ids, rebuilt.stable_ids);
+        db.games.items = rebuilt.games.items;
+        db.games.reindex();
+    }
+    #[test]
+    fn mutations() {
+        let mut db: DataBase = "Game\tToto\nTags\tindie, rpg\nEngine\tFNA\n\
+                                Game\tTiti\nTags\tindie\nStore\thttps://gog.com/a"
+            .parse()
+            .unwrap();
+        let mut game = Game::new();
+        game.name = "Toto".to_string();
+        game.tags = vec!["indie".to_string(), "indie".to_string()];
+        game.engine = "Unity".to_string();
+        let change = db.insert_game(game);
+        assert_eq!(change.kind, ChangeKind::Inserted);
+        assert_eq!(change.fields, vec!["Game", "Engine", "Tags"]);
+        assert_eq!(db.get_game_stable_id(3).unwrap().as_str(), "toto--game-2");
+        check_consistency(&mut db);
+        let change = db
+            .update_game(1, |game| {
+                game.id = 42;
+                game.tags = vec!["rpg".to_string(), "new".to_string()];
+                game.engine = String::new();
+            })
+            .unwrap();
+        assert_eq!(change.fields, vec!["Engine", "Tags"]);
+        assert_eq!(change.created, vec![("Tags", "new".to_string())]);
+        assert_eq!(change.removed, vec![("Engine", "FNA".to_string())]);
+        assert_eq!(db.get_game_by_id(1).unwrap().id, 1);
+        check_consistency(&mut db);
+        let change = db.rename_game(2, "Tata").unwrap();
+        assert_eq!(change.fields, vec!["Game"]);
+        assert_eq!(db.get_game_by_name("Tata").unwrap().id, 2);
+        check_consistency(&mut db);
+        let change = db.remove_game(2).unwrap();
+        assert_eq!(change.removed, vec![("Store", "gog.com".to_string())]);
+        assert!(db.remove_game(2).is_none());
+        assert!(db.update_game(2, |_| ()).is_none());
+        check_consistency(&mut db);
+        assert_eq!(db.tags.get_item_by_name("indie").unwrap().games, vec![3]);
+        // removing the repeated value keeps the game linked to it
+        let change = db
+            .update_game(3, |game| game.tags = vec!["indie".to_string()])
+            .unwrap();
+        assert!(change.removed.is_empty());
+        assert_eq!(db.tags.get_item_by_name("indie").unwrap().games, vec![3]);
+        check_consistency(&mut db);
+        assert_eq!(db.insert_game(Game::new()).id, 4);
+        check_consistency(&mut db);
+    }
+    #[test]
+    fn taxonomy_maintenance() {
+        let mut db: DataBase = "Game\tToto\nTags\tshmup, indie\nGenre\tPuzzle Platformer\n\
+                                Game\tTiti\nTags\tshoot em up, shmup\nGenre\tPuzzle, RPG\n\
+                                Game\tTata\nTags\tindie"
+            .parse()
+            .unwrap();
+        let changes = db.merge_tags(&["shmup", "shoot em up"], "shoot 'em up");
+        let ids: Vec<usize> = changes.iter().map(|change| change.id).collect();
+        assert_eq!(ids, vec![1, 2]);
+        assert_eq!(db.get_game_by_id(2).unwrap().tags, vec!["shoot 'em up"]);
+        assert_eq!(db.get_games_by_tag("shoot 'em up").count, 2);
+        check_consistency(&mut db);
+        assert!(db.rename_tag("unknown", "other").is_empty());
+        assert!(db.rename_tag("indie", "indie").is_empty());
+        let changes = db.split_genre("Puzzle Platformer", &["Puzzle", " Platformer", ""]);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            changes[0].created,
+            vec![("Genre", "Platformer".to_string())]
+        );
+        assert_eq!(
+            db.genres.get_item_by_name("Puzzle").unwrap().games,
+            vec![1, 2]
+        );
+        check_consistency(&mut db);
+        let changes = db.rename_genre("RPG", "Role Playing");
+        assert_eq!(changes[0].fields, vec!["Genre"]);
+        assert_eq!(
+            db.get_game_by_id(2).unwrap().genres,
+            vec!["Puzzle", "Role Playing"]
+        );
+        check_consistency(&mut db);
+    }
 }
@@ -0,0 +1,198 @@
+//! # Errors returned by the library
+use serde_json;
+use std::error;
+use std::fmt;
+use std::io;
+
+/* ------------------------ PARSE ERROR -----------------------*/
+/// Describes what went wrong while reading the database.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ParseErrorKind {
+    /// The key on the left hand side of the line is neither a known field
+    /// nor a valid name for an extra field.
+    UnknownField,
+    /// The line is not valid UTF-8.
+    InvalidUtf8,
+    /// The line contains more than one tab, the extra part is ignored.
+    TooManyTabs,
+    /// The line appears before the first Game line.
+    OrphanField,
+    /// The value of a typed field (e.g. a date) cannot be parsed.
+    InvalidValue,
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseErrorKind::UnknownField => write!(f, "unknown field"),
+            ParseErrorKind::InvalidUtf8 => write!(f, "invalid UTF-8 in line"),
+            ParseErrorKind::TooManyTabs => write!(f, "too many tabs, ignoring"),
+            ParseErrorKind::OrphanField => write!(f, "no game for field"),
+            ParseErrorKind::InvalidValue => write!(f, "invalid value"),
+        }
+    }
+}
+
+/// # Represent an error encountered while parsing the database
+/// The error keeps track of where the problem occured (file, line, column)
+/// as well as the offending key and a hint on how to fix it.
+/// The location is only known when the error comes from a database
+/// file, this is why `file`, `line` and `column` are optional.
+/// ```
+/// use pobsdlib::models::Field;
+/// use pobsdlib::ParseErrorKind;
+///
+/// let error = Field::from("Tag\ttag1, tag2").unwrap_err();
+/// assert_eq!(error.kind, ParseErrorKind::UnknownField);
+/// assert_eq!(error.key, "Tag");
+/// assert_eq!(error.column, Some(1));
+/// assert_eq!(error.hint, "did you mean Tags?");
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct ParseError {
+    /// The kind of error.
+    pub kind: ParseErrorKind,
+    /// The file being parsed, if any.
+    pub file: Option<String>,
+    /// The line number (starting at 1), if known.
+    pub line: Option<usize>,
+    /// The column number (starting at 1), if known.
+    pub column: Option<usize>,
+    /// The offending key.
+    pub key: String,
+    /// A hint on how to fix the error.
+    pub hint: String,
+}
+
+impl ParseError {
+    /// Create a new error without location.
+    pub fn new(kind: ParseErrorKind, key: &str, hint: &str) -> Self {
+        Self {
+            kind,
+            file: None,
+            line: None,
+            column: None,
+            key: key.to_string(),
+            hint: hint.to_string(),
+        }
+    }
+    /// Sets the column of the error.
+    pub fn with_column(mut self, column: usize) -> Self {
+        self.column = Some(column);
+        self
+    }
+    /// Sets the line of the error.
+    pub fn with_line(mut self, line: usize) -> Self {
+        self.line = Some(line);
+        self
+    }
+    /// Sets the file of the error.
+    pub fn with_file(mut self, file: &str) -> Self {
+        self.file = Some(file.to_string());
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file)?;
+        }
+        if let Some(line) = self.line {
+            write!(f, "{}:", line)?;
+        }
+        if let Some(column) = self.column {
+            write!(f, "{}:", column)?;
+        }
+        if self.file.is_some() || self.line.is_some() || self.column.is_some() {
+            write!(f, " ")?;
+        }
+        write!(f, "{} {}", self.kind, self.key)?;
+        if !self.hint.is_empty() {
+            write!(f, " ({})", self.hint)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for ParseError {}
+
+/* ------------------------ ERROR -----------------------*/
+/// # Represent any error returned by the library
+#[derive(Debug)]
+pub enum Error {
+    /// The database could not be read.
+    Io(io::Error),
+    /// The database could not be parsed (including invalid UTF-8).
+    Parse(ParseError),
+    /// The JSON export could not be read or is inconsistent.
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(error) => write!(f, "io error: {}", error),
+            Error::Parse(error) => write!(f, "parse error: {}", error),
+            Error::Json(error) => write!(f, "json error: {}", error),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            Error::Parse(error) => Some(error),
+            Error::Json(error) => Some(error),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(error: ParseError) -> Self {
+        Error::Parse(error)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error::Json(error)
+    }
+}
+
+/*-------------------------- TESTS --------------------------------*/
+#[cfg(test)]
+mod test_parse_error {
+    use super::*;
+    #[test]
+    fn display_without_location() {
+        let error = ParseError::new(ParseErrorKind::UnknownField, "Toto", "");
+        assert_eq!(error.to_string(), "unknown field Toto");
+    }
+    #[test]
+    fn display_with_location() {
+        let error = ParseError::new(ParseErrorKind::UnknownField, "Tag", "did you mean Tags?")
+            .with_file("games.db")
+            .with_line(12)
+            .with_column(1);
+        assert_eq!(
+            error.to_string(),
+            "games.db:12:1: unknown field Tag (did you mean Tags?)"
+        );
+    }
+    #[test]
+    fn error_from_parse_error() {
+        let error = ParseError::new(ParseErrorKind::UnknownField, "Toto", "");
+        match Error::from(error.clone()) {
+            Error::Parse(inner) => assert_eq!(inner, error),
+            _ => panic!("Should be a parse error"),
+        }
+    }
+}
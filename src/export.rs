@@ -0,0 +1,154 @@
+//! # Export the database to JSON
+use crate::collections::{DataBase, ItemCollection};
+use crate::models::{Game, Item};
+use std::io::{self, Read};
+
+/// Version of the JSON export format, increased on incompatible changes.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/* ------------------------ SECTIONS -------------------------*/
+/// # Select the collections written in a JSON export
+/// All the collections are selected by default.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Sections {
+    pub games: bool,
+    pub tags: bool,
+    pub genres: bool,
+}
+
+impl Default for Sections {
+    fn default() -> Self {
+        Self {
+            games: true,
+            tags: true,
+            genres: true,
+        }
+    }
+}
+
+impl Sections {
+    /// Returns a selection without any collection.
+    pub fn none() -> Self {
+        Self {
+            games: false,
+            tags: false,
+            genres: false,
+        }
+    }
+}
+
+/* ------------------------ EXPORT -------------------------*/
+/// # Represent a JSON export of the database
+/// The selected collections are wrapped in an envelope giving the version
+/// of the format, the number of games in the database and, when known, the
+/// hash of the database file they were read from.
+/// ```
+/// use pobsdlib::collections::DataBase;
+/// use pobsdlib::export::{Export, Sections, SCHEMA_VERSION};
+/// let db: DataBase = "Game\tToto\nTags\ttag1, tag2".parse().unwrap();
+/// let sections = Sections { games: false, ..Default::default() };
+/// let export = Export::new(&db, sections).with_source_hash("fnv1a64:0");
+/// let json = serde_json::to_value(&export).unwrap();
+/// assert_eq!(json["schema_version"], SCHEMA_VERSION);
+/// assert_eq!(json["game_count"], 1);
+/// assert_eq!(json["tags"]["count"], 2);
+/// assert!(json.get("games").is_none());
+/// ```
+#[derive(Serialize, Debug)]
+pub struct Export<'a> {
+    /// The version of the format (see `SCHEMA_VERSION`).
+    pub schema_version: u32,
+    /// The number of games in the database, even if they are not exported.
+    pub game_count: usize,
+    /// The hash of the database file (see `source_hash`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_hash: Option<String>,
+    /// The games collection, if selected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub games: Option<&'a ItemCollection<Game>>,
+    /// The tags collection, if selected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<&'a ItemCollection<Item>>,
+    /// The genres collection, if selected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub genres: Option<&'a ItemCollection<Item>>,
+}
+
+impl<'a> Export<'a> {
+    /// Create an export of the selected collections.
+    pub fn new(database: &'a DataBase, sections: Sections) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            game_count: database.get_games_count(),
+            source_hash: None,
+            games: if sections.games {
+                Some(&database.games)
+            } else {
+                None
+            },
+            tags: if sections.tags {
+                Some(&database.tags)
+            } else {
+                None
+            },
+            genres: if sections.genres {
+                Some(&database.genres)
+            } else {
+                None
+            },
+        }
+    }
+    /// Sets the hash of the database file.
+    pub fn with_source_hash(mut self, hash: &str) -> Self {
+        self.source_hash = Some(hash.to_string());
+        self
+    }
+}
+
+/* ------------------------ SOURCE HASH -------------------------*/
+/// Returns the FNV-1a (64 bits) hash of the database, written as
+/// `fnv1a64:` followed by 16 hexadecimal digits.
+/// The hash is meant to detect that the database changed, it is not
+/// a cryptographic hash.
+/// ```
+/// use pobsdlib::export::source_hash;
+/// assert_eq!(source_hash("".as_bytes()).unwrap(), "fnv1a64:cbf29ce484222325");
+/// assert_eq!(source_hash("a".as_bytes()).unwrap(), "fnv1a64:af63dc4c8601ec8c");
+/// ```
+pub fn source_hash<R: Read>(mut reader: R) -> io::Result<String> {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut buffer = [0u8; 8192];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        for byte in &buffer[..read] {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    Ok(format!("fnv1a64:{:016x}", hash))
+}
+
+/*-------------------------- TESTS --------------------------------*/
+#[cfg(test)]
+mod test_export {
+    use super::*;
+    use serde_json;
+    #[test]
+    fn all_sections() {
+        let db: DataBase = "Game\tToto\nGenre\tRPG\nTags\ttag1".parse().unwrap();
+        let json = serde_json::to_value(Export::new(&db, Sections::default())).unwrap();
+        assert_eq!(json["games"]["count"], 1);
+        assert_eq!(json["tags"]["items"][0]["games"][0], 1);
+        assert_eq!(json["genres"]["items"][0]["name"], "RPG");
+        assert!(json.get("source_hash").is_none());
+    }
+    #[test]
+    fn no_section() {
+        let db: DataBase = "Game\tToto".parse().unwrap();
+        let json = serde_json::to_string(&Export::new(&db, Sections::none())).unwrap();
+        assert_eq!(json, r#"{"schema_version":1,"game_count":1}"#);
+    }
+}
@@ -0,0 +1,210 @@
+//! # Count the values of the games
+use crate::models::GameTraits;
+use std::collections::HashMap;
+
+/* ------------------------ FACET -----------------------*/
+/// # The fields the games can be counted by
+/// The facets give, for a set of games, the number of games using each
+/// value of a field, e.g. "Engine: FNA (12), Unity (30)".
+/// ```
+/// use pobsdlib::collections::DataBase;
+/// use pobsdlib::facets::{Facet, FacetCount};
+/// let db = DataBase::open("tests/data/test-games.db").unwrap();
+/// let games = db.get_games_by_tag("indie");
+/// let engines = games.get_facet(Facet::Engine);
+/// assert_eq!(engines, vec![FacetCount { value: "FNA".to_string(), count: 2 }]);
+/// let facets = games.get_facets();
+/// assert_eq!(facets.decade[0].value, "2010s");
+/// assert_eq!(facets.store[0].value, "store.steampowered.com");
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Facet {
+    Tags,
+    Genre,
+    Engine,
+    Runtime,
+    /// The host of the store urls, without a leading "www."
+    /// (e.g. "gog.com" for "https://www.gog.com/game/").
+    Store,
+    Year,
+    /// The decade of the year (e.g. "2010s" for 2014).
+    Decade,
+    Dev,
+    Pub,
+}
+
+impl Facet {
+    /// Returns the name of the field counted.
+    pub fn field_name(&self) -> &'static str {
+        match self {
+            Facet::Tags => "Tags",
+            Facet::Genre => "Genre",
+            Facet::Engine => "Engine",
+            Facet::Runtime => "Runtime",
+            Facet::Store => "Store",
+            Facet::Year | Facet::Decade => "Year",
+            Facet::Dev => "Dev",
+            Facet::Pub => "Pub",
+        }
+    }
+    /// Returns the values of the game counted by the facet, without the
+    /// empty ones.
+    pub fn values<T: GameTraits>(&self, game: &T) -> Vec<String> {
+        let values = match game.get_field(self.field_name()) {
+            Ok(field) => field.values(),
+            Err(_) => return Vec::new(),
+        };
+        values
+            .into_iter()
+            .map(str::trim)
+            .filter_map(|value| match self {
+                Facet::Store => store_host(value),
+                Facet::Decade => decade(value),
+                _ => Some(value.to_string()),
+            })
+            .filter(|value| !value.is_empty())
+            .collect()
+    }
+}
+
+/// Returns the host of the url without a leading "www.", None if the url has no host.
+fn store_host(url: &str) -> Option<String> {
+    let url = match url.find("://") {
+        Some(index) => &url[index + 3..],
+        None => url,
+    };
+    let host = url.split(['/', ':', '?', '#']).next()?.to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+    if host.split('.').filter(|label| !label.is_empty()).count() < 2 {
+        return None;
+    }
+    Some(host.to_string())
+}
+
+/// Returns the decade of the year, None if it is not a number.
+fn decade(year: &str) -> Option<String> {
+    let year: u32 = year.parse().ok()?;
+    Some(format!("{}s", year - year % 10))
+}
+
+/* ------------------------ COUNTS -----------------------*/
+/// # Represent the number of games using a value
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: usize,
+}
+
+/// Returns the number of games using each value, the most used first,
+/// then in alphabetical order. A game having a value twice is counted once.
+pub fn count_values<T: GameTraits>(games: &[T], facet: Facet) -> Vec<FacetCount> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for game in games {
+        let mut values = facet.values(game);
+        values.sort();
+        values.dedup();
+        for value in values {
+            *counts.entry(value).or_insert(0) += 1;
+        }
+    }
+    let mut counts: Vec<FacetCount> = counts
+        .into_iter()
+        .map(|(value, count)| FacetCount { value, count })
+        .collect();
+    counts.sort_by(|left, right| {
+        right
+            .count
+            .cmp(&left.count)
+            .then_with(|| left.value.cmp(&right.value))
+    });
+    counts
+}
+
+/// # Represent the counts of all the facets of a set of games
+/// See `ItemCollection::get_facets`.
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct Facets {
+    pub tags: Vec<FacetCount>,
+    pub genres: Vec<FacetCount>,
+    pub engine: Vec<FacetCount>,
+    pub runtime: Vec<FacetCount>,
+    pub store: Vec<FacetCount>,
+    pub year: Vec<FacetCount>,
+    pub decade: Vec<FacetCount>,
+    pub dev: Vec<FacetCount>,
+    pub publi: Vec<FacetCount>,
+}
+
+impl Facets {
+    /// Counts all the facets of the games.
+    pub fn new<T: GameTraits>(games: &[T]) -> Self {
+        Self {
+            tags: count_values(games, Facet::Tags),
+            genres: count_values(games, Facet::Genre),
+            engine: count_values(games, Facet::Engine),
+            runtime: count_values(games, Facet::Runtime),
+            store: count_values(games, Facet::Store),
+            year: count_values(games, Facet::Year),
+            decade: count_values(games, Facet::Decade),
+            dev: count_values(games, Facet::Dev),
+            publi: count_values(games, Facet::Pub),
+        }
+    }
+}
+
+/*-------------------------- TESTS --------------------------------*/
+#[cfg(test)]
+mod test_facets {
+    use super::*;
+    use utils::load_test_games;
+    #[test]
+    fn hosts() {
+        assert_eq!(
+            store_host("https://store.steampowered.com/app/211440/").unwrap(),
+            "store.steampowered.com"
+        );
+        assert_eq!(store_host("http://www.gog.com:80").unwrap(), "gog.com");
+        assert_eq!(
+            store_host("Zarkonnen.itch.io/airships").unwrap(),
+            "zarkonnen.itch.io"
+        );
+        assert_eq!(store_host("https://www.bbc.co.uk/").unwrap(), "bbc.co.uk");
+        assert!(store_host("https://localhost/").is_none());
+        assert!(store_host("").is_none());
+    }
+    #[test]
+    fn decades() {
+        assert_eq!(decade("2014").unwrap(), "2010s");
+        assert_eq!(decade("1990").unwrap(), "1990s");
+        assert!(decade("").is_none());
+        assert!(decade("soon").is_none());
+    }
+    #[test]
+    fn per_element() {
+        let games = load_test_games(
+            "Game\tToto\nTags\tindie, rpg, indie\nYear\t2014\n\
+             Store\thttps://www.gog.com/a https://gog.com/b\n\
+             Game\tTiti\nTags\tindie\nYear\t2019\n\
+             Game\tTata",
+        )
+        .items;
+        let count = |value: &str, count| FacetCount {
+            value: value.to_string(),
+            count,
+        };
+        assert_eq!(
+            count_values(&games, Facet::Tags),
+            vec![count("indie", 2), count("rpg", 1)]
+        );
+        assert_eq!(
+            count_values(&games, Facet::Year),
+            vec![count("2014", 1), count("2019", 1)]
+        );
+        assert_eq!(count_values(&games, Facet::Decade), vec![count("2010s", 2)]);
+        assert_eq!(
+            count_values(&games, Facet::Store),
+            vec![count("gog.com", 1)]
+        );
+        assert!(count_values(&games, Facet::Engine).is_empty());
+    }
+}
@@ -4,8 +4,20 @@
 //!
 #[macro_use]
 extern crate serde_derive;
+extern crate serde;
 extern crate serde_json;
+extern crate regex;
 mod utils;
 // public api
+pub mod autocomplete;
 pub mod collections;
+pub mod error;
+pub mod export;
+pub mod facets;
+pub mod matching;
 pub mod models;
+pub mod parser;
+pub mod query;
+pub mod search;
+pub mod sorting;
+pub use error::{Error, ParseError, ParseErrorKind};
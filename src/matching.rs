@@ -0,0 +1,115 @@
+//! # Match the values of the fields
+use crate::utils::edit_distance;
+use regex::{self, RegexBuilder};
+
+/* ------------------------ MATCH MODE -----------------------*/
+/// How a value is compared with the values of a field.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum MatchMode {
+    /// The value of the field is the value.
+    Exact,
+    /// The value of the field starts with the value.
+    Prefix,
+    /// The value of the field contains the value.
+    #[default]
+    Substring,
+    /// The value of the field contains the value as whole words,
+    /// i.e. not preceded nor followed by an alphanumeric character.
+    WholeWord,
+    /// The value of the field matches the value, being a regular
+    /// expression with the syntax of the regex crate.
+    Regex,
+    /// The value of the field is at most the given number of edits
+    /// (insertions, deletions or substitutions) away from the value.
+    Fuzzy(usize),
+}
+
+/// # Represent a value to match with the values of the fields
+/// The comparisons are not case sensitive.
+/// ```
+/// use pobsdlib::matching::{MatchMode, Pattern};
+/// let pattern = Pattern::new("indie", MatchMode::Exact).unwrap();
+/// assert!(pattern.is_match("Indie"));
+/// assert!(!pattern.is_match("nonindie"));
+/// let pattern = Pattern::new("^shoot.*up$", MatchMode::Regex).unwrap();
+/// assert!(pattern.is_match("Shoot 'em up"));
+/// let pattern = Pattern::new("platfromer", MatchMode::Fuzzy(2)).unwrap();
+/// assert!(pattern.is_match("Platformer"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    mode: MatchMode,
+    value: String,
+    regex: Option<regex::Regex>,
+}
+
+impl Pattern {
+    /// Create a pattern. Returns a regex::Error if the mode is
+    /// `MatchMode::Regex` and the value is not a valid regular expression.
+    pub fn new(value: &str, mode: MatchMode) -> Result<Self, regex::Error> {
+        let regex = match mode {
+            MatchMode::Regex => Some(RegexBuilder::new(value).case_insensitive(true).build()?),
+            _ => None,
+        };
+        Ok(Self {
+            mode,
+            value: value.to_lowercase(),
+            regex,
+        })
+    }
+    /// Returns the match mode.
+    pub fn mode(&self) -> MatchMode {
+        self.mode
+    }
+    /// Returns true if the text matches the pattern.
+    pub fn is_match(&self, text: &str) -> bool {
+        if let Some(regex) = &self.regex {
+            return regex.is_match(text);
+        }
+        let text = text.to_lowercase();
+        match self.mode {
+            MatchMode::Exact => text == self.value,
+            MatchMode::Prefix => text.starts_with(&self.value),
+            MatchMode::Substring => text.contains(&self.value),
+            MatchMode::WholeWord => text.match_indices(&self.value).any(|(start, value)| {
+                let before = text[..start].chars().next_back();
+                let after = text[start + value.len()..].chars().next();
+                !before.is_some_and(char::is_alphanumeric)
+                    && !after.is_some_and(char::is_alphanumeric)
+            }),
+            MatchMode::Fuzzy(distance) => edit_distance(&text, &self.value) <= distance,
+            MatchMode::Regex => false,
+        }
+    }
+}
+
+/*-------------------------- TESTS --------------------------------*/
+#[cfg(test)]
+mod test_pattern {
+    use super::*;
+    fn is_match(value: &str, mode: MatchMode, text: &str) -> bool {
+        Pattern::new(value, mode).unwrap().is_match(text)
+    }
+    #[test]
+    fn modes() {
+        assert!(is_match("indie", MatchMode::Exact, "INDIE"));
+        assert!(!is_match("indie", MatchMode::Exact, "nonindie"));
+        assert!(is_match("pix", MatchMode::Prefix, "Pixel art"));
+        assert!(!is_match("art", MatchMode::Prefix, "Pixel art"));
+        assert!(is_match("el a", MatchMode::Substring, "Pixel art"));
+        assert!(is_match("art", MatchMode::WholeWord, "Pixel art"));
+        assert!(is_match("pixel art", MatchMode::WholeWord, "pixel art, 2D"));
+        assert!(!is_match("indie", MatchMode::WholeWord, "nonindie"));
+        assert!(is_match("indie", MatchMode::WholeWord, "non-indie"));
+        assert!(!is_match("shmup", MatchMode::Fuzzy(1), "shmpu"));
+        assert!(is_match("shmup", MatchMode::Fuzzy(2), "shmpu"));
+        assert!(is_match("^s.m", MatchMode::Regex, "SHMUP"));
+        assert!(is_match(r"^\w+ [é]$", MatchMode::Regex, "Pokémon É"));
+        assert!(!is_match("^(cat|dog)s?$", MatchMode::Regex, "cow"));
+    }
+    #[test]
+    fn invalid_regex() {
+        assert!(Pattern::new("a(", MatchMode::Regex).is_err());
+        assert!(Pattern::new("a(", MatchMode::Substring).is_ok());
+    }
+}
@@ -1,11 +1,27 @@
-use crate::utils::split_line;
+use crate::error::{ParseError, ParseErrorKind};
+use crate::matching::{MatchMode, Pattern};
+use crate::utils::{is_extra_field_name, split_line, unknown_field_hint};
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// Names of the fields known by the library, in the database order.
+pub const FIELD_NAMES: [&str; 17] = [
+    "Game", "Cover", "Engine", "Setup", "Runtime", "Store", "Hints", "Genre", "Tags", "Year",
+    "Dev", "Pub", "Version", "Status", "Added", "Updated", "IgdbId",
+];
+
+/// Fields only present in the newer versions of the database.
+const DATED_FIELD_NAMES: [&str; 3] = ["Added", "Updated", "IgdbId"];
 
 /* ------------------------ FIELD ENUM -----------------------*/
 /// # Represent a field generated form a line of the game database
-/// There is three different variants:
+/// There is four different variants:
 /// * a first variant for Game entries;
 /// * a second variant for entries related to single items (i.e. Engine);
-/// * a third variant for entries related to multiple items (i.e Tags).
+/// * a third variant for entries related to multiple items (i.e Tags);
+/// * a fourth variant for entries not known by the library.
 ///
 ///
 /// ## Field::NewGame
@@ -15,7 +31,7 @@ use crate::utils::split_line;
 /// use pobsdlib::models::Field;
 ///
 /// let line_str = "Game\tName of the game";
-/// let field = Field::from(line_str);
+/// let field = Field::from(line_str).unwrap();
 ///
 /// assert_eq!(field,Field::NewGame(&"Name of the game"));
 /// assert_eq!(field.as_line(),line_str);
@@ -28,7 +44,7 @@ use crate::utils::split_line;
 /// use pobsdlib::models::Field;
 ///
 /// let line_str = "Engine\tEngine name";
-/// let field = Field::from(line_str);
+/// let field = Field::from(line_str).unwrap();
 ///
 /// assert_eq!(field,Field::SingleItem(&"Engine",&"Engine name"));
 /// assert_eq!(field.as_line(),line_str);
@@ -41,38 +57,58 @@ use crate::utils::split_line;
 /// use pobsdlib::models::Field;
 ///
 /// let line_str = "Tags\ttag1, tag2";
-/// let field = Field::from(line_str);
+/// let field = Field::from(line_str).unwrap();
 ///
 /// assert_eq!(field,Field::MultipleItems(&"Tags",vec![&"tag1",&"tag2"]));
 /// assert_eq!(field.as_line(),line_str);
 /// ```
 /// Note that while Tags and Genres are coma separated values, Stores are space separated ones.
 /// This is handled by the `Field::from` method.
+///
+/// ## Field::Extra
+/// A line corresponding to a field unknown to the library (e.g. a field
+/// added upstream after this version) will produce a Field::Extra
+/// storing the name of the field and its raw value.
+/// ```
+/// use pobsdlib::models::Field;
+///
+/// let line_str = "NewField\tsome value";
+/// let field = Field::from(line_str).unwrap();
+///
+/// assert_eq!(field,Field::Extra(&"NewField",&"some value"));
+/// assert_eq!(field.as_line(),line_str);
+/// ```
+/// Only capitalized alphanumeric names are accepted this way.
 #[derive(PartialEq, Debug)]
 pub enum Field<'a> {
     NewGame(&'a str),
     SingleItem(&'a str, &'a str),
     MultipleItems(&'a str, Vec<&'a str>),
+    Extra(&'a str, &'a str),
 }
 
 impl<'a> Field<'a> {
-    /// Try to convert a line of the database in a Field enum (see exemple above). Panic if it cannot.
+    /// Try to convert a line of the database in a Field enum (see exemple above).
+    /// Returns a ParseError if the field name is malformed.
     /// ```
     /// use pobsdlib::models::Field;
     ///
     /// let line_str = "Tags\ttag1, tag2";
-    /// let field = Field::from(line_str);
+    /// let field = Field::from(line_str).unwrap();
     ///
     /// assert_eq!(field,Field::MultipleItems(&"Tags",vec![&"tag1",&"tag2"]));
+    /// assert!(Field::from("not a field\tvalue").is_err());
     /// ```
-    pub fn from(line: &'a str) -> Self {
+    pub fn from(line: &'a str) -> Result<Self, ParseError> {
         // split the line in a left and right hand sides
         let (left, right) = split_line(line);
         // use the left hand side to discriminate between single and multiple item lines
-        match left {
+        let field = match left {
             "Game" => Field::NewGame(right),
             "Cover" | "Engine" | "Setup" | "Runtime" | "Hints" | "Year" | "Dev" | "Pub"
-            | "Version" | "Status" => Field::SingleItem(left, right),
+            | "Version" | "Status" | "Added" | "Updated" | "IgdbId" => {
+                Field::SingleItem(left, right)
+            }
             "Store" => {
                 let mut items: Vec<&str> = Vec::new();
                 for item in right.split(' ') {
@@ -87,30 +123,63 @@ impl<'a> Field<'a> {
                 }
                 Field::MultipleItems(left, items)
             }
-            _ => panic!("Unkown filed {}", left),
+            _ if is_extra_field_name(left) => Field::Extra(left, right),
+            _ => {
+                return Err(ParseError::new(
+                    ParseErrorKind::UnknownField,
+                    left,
+                    &unknown_field_hint(left),
+                )
+                .with_column(1))
+            }
+        };
+        Ok(field)
+    }
+    /// Returns the values of the field, several values for Tags, Genre and Store.
+    /// ```
+    /// use pobsdlib::models::Field;
+    /// let field = Field::from("Tags\ttag1, tag2").unwrap();
+    /// assert_eq!(field.values(), vec!["tag1", "tag2"]);
+    /// ```
+    pub fn values(&self) -> Vec<&'a str> {
+        match self {
+            Field::NewGame(value) => vec![*value],
+            Field::SingleItem(_, value) | Field::Extra(_, value) => vec![*value],
+            Field::MultipleItems(_, values) => values.clone(),
         }
     }
     /// Returns the string corresponding to the line in the database
     /// ```
     /// use pobsdlib::models::Field;
     /// let input = "Engine\tSuper engine";
-    /// let field = Field::from(&input);
+    /// let field = Field::from(&input).unwrap();
     /// assert_eq!(field.as_line(), input);
     /// let input = "Genre\tGe1, Ge2";
-    /// let field = Field::from(&input);
+    /// let field = Field::from(&input).unwrap();
+    /// assert_eq!(field.as_line(), input);
+    /// // empty fields are written without tab
+    /// let input = "Engine";
+    /// let field = Field::from(&input).unwrap();
     /// assert_eq!(field.as_line(), input);
     /// ```
     pub fn as_line(&'a self) -> String {
-        match self {
-            Field::NewGame(name) => vec!["Game", name].join("\t"),
-            Field::SingleItem(left, right) => vec![left.to_owned(), right].join("\t"),
+        let (left, right) = match self {
+            Field::NewGame(name) => ("Game", name.to_string()),
+            Field::SingleItem(left, right) | Field::Extra(left, right) => {
+                (*left, right.to_string())
+            }
             Field::MultipleItems(left, right) => {
                 if left.eq(&"Store") {
-                    vec![left.to_owned(), right.join(" ").as_str()].join("\t")
+                    (*left, right.join(" "))
                 } else {
-                    vec![left.to_owned(), right.join(", ").as_str()].join("\t")
+                    (*left, right.join(", "))
                 }
             }
+        };
+        if right.is_empty() {
+            left.to_string()
+        } else {
+            [left, right.as_str()].join("\t")
         }
     }
 }
@@ -119,6 +188,10 @@ impl<'a> Field<'a> {
 /// This trait is needed if you use ItemCollection with Item.
 pub trait ItemTraits {
     fn get_name(&self) -> &str;
+    /// Returns the id of the item, 0 if the item has no id (the default).
+    fn get_id(&self) -> usize {
+        0
+    }
 }
 /// This trait is needed if you use ItemCollection with mutable Item.
 /// It implies ItemTraits
@@ -141,7 +214,7 @@ pub trait ItemTraitsMut: ItemTraits {
 ///
 /// assert_eq!(item.get_name(),"Item name");
 /// ```
-#[derive(Default, PartialEq)]
+#[derive(Serialize, Deserialize, Default, PartialEq, Debug)]
 pub struct Item {
     /// The id of the tag.
     pub id: usize,
@@ -163,6 +236,10 @@ impl ItemTraits for Item {
     fn get_name(&self) -> &str {
         &self.name
     }
+    /// Returns the id of the item.
+    fn get_id(&self) -> usize {
+        self.id
+    }
 }
 
 impl ItemTraits for &Item {
@@ -170,6 +247,10 @@ impl ItemTraits for &Item {
     fn get_name(&self) -> &str {
         &self.name
     }
+    /// Returns the id of the item.
+    fn get_id(&self) -> usize {
+        self.id
+    }
 }
 
 impl ItemTraits for &mut Item {
@@ -177,6 +258,10 @@ impl ItemTraits for &mut Item {
     fn get_name(&self) -> &str {
         &self.name
     }
+    /// Returns the id of the item.
+    fn get_id(&self) -> usize {
+        self.id
+    }
 }
 
 impl ItemTraitsMut for Item {
@@ -193,19 +278,435 @@ impl ItemTraitsMut for &mut Item {
     }
 }
 
+/* ------------------------ DATE -------------------------*/
+/// # Represent a date of the database
+/// Dates are written following ISO 8601 (YYYY-MM-DD).
+/// ```
+/// use pobsdlib::models::Date;
+/// let date: Date = "2022-02-28".parse().unwrap();
+/// assert_eq!(date.year(), 2022);
+/// assert_eq!(date.month(), 2);
+/// assert_eq!(date.day(), 28);
+/// assert_eq!(date.as_str(), "2022-02-28");
+/// assert!("2022-02-29".parse::<Date>().is_err());
+/// assert!(Date::new(2022, 3, 1).unwrap() > date);
+/// ```
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Date {
+    year: u16,
+    month: u8,
+    day: u8,
+    text: String,
+}
+
+impl Date {
+    /// Returns the date if it exists, None otherwise.
+    pub fn new(year: u16, month: u8, day: u8) -> Option<Self> {
+        let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+        let days = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if leap => 29,
+            2 => 28,
+            _ => return None,
+        };
+        if day == 0 || day > days {
+            return None;
+        }
+        Some(Self {
+            year,
+            month,
+            day,
+            text: format!("{:04}-{:02}-{:02}", year, month, day),
+        })
+    }
+    /// Returns the year.
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+    /// Returns the month (from 1 to 12).
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+    /// Returns the day of the month (from 1 to 31).
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+    /// Returns the date as written in the database.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+}
+
+impl FromStr for Date {
+    type Err = ParseError;
+    /// Parses a YYYY-MM-DD date. Returns a ParseError if it cannot.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error = || {
+            ParseError::new(
+                ParseErrorKind::InvalidValue,
+                text,
+                "dates must be written as YYYY-MM-DD",
+            )
+        };
+        let parts: Vec<&str> = text.split('-').collect();
+        if parts.len() != 3
+            || parts[0].len() != 4
+            || parts[1].len() != 2
+            || parts[2].len() != 2
+            || !parts
+                .iter()
+                .all(|part| part.chars().all(|c| c.is_ascii_digit()))
+        {
+            return Err(error());
+        }
+        let year = parts[0].parse().map_err(|_| error())?;
+        let month = parts[1].parse().map_err(|_| error())?;
+        let day = parts[2].parse().map_err(|_| error())?;
+        Self::new(year, month, day).ok_or_else(error)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+impl Serialize for Date {
+    /// Serializes the date as a YYYY-MM-DD string.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.text)
+    }
+}
+
+impl<'de> Deserialize<'de> for Date {
+    /// Deserializes a YYYY-MM-DD string.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(de::Error::custom)
+    }
+}
+
+/* ------------------------ IGDB ID -------------------------*/
+/// # Represent the id of a game in the IGDB database
+/// ```
+/// use pobsdlib::models::IgdbId;
+/// let id: IgdbId = "1234".parse().unwrap();
+/// assert_eq!(id.id(), 1234);
+/// assert_eq!(id.as_str(), "1234");
+/// assert!("12a".parse::<IgdbId>().is_err());
+/// ```
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct IgdbId {
+    id: u64,
+    text: String,
+}
+
+impl IgdbId {
+    /// Create an IgdbId from its numeric value.
+    pub fn new(id: u64) -> Self {
+        Self {
+            id,
+            text: id.to_string(),
+        }
+    }
+    /// Returns the numeric value of the id.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+    /// Returns the id as written in the database.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+}
+
+impl FromStr for IgdbId {
+    type Err = ParseError;
+    /// Parses a numeric id. Returns a ParseError if it cannot.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        // u64::from_str accepts a leading +
+        match text.parse() {
+            Ok(id) if text.chars().all(|c| c.is_ascii_digit()) => Ok(Self::new(id)),
+            _ => Err(ParseError::new(
+                ParseErrorKind::InvalidValue,
+                text,
+                "IGDB ids must be positive integers",
+            )),
+        }
+    }
+}
+
+impl fmt::Display for IgdbId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+impl Serialize for IgdbId {
+    /// Serializes the id as a number.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.id)
+    }
+}
+
+impl<'de> Deserialize<'de> for IgdbId {
+    /// Deserializes a number.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Self::new(u64::deserialize(deserializer)?))
+    }
+}
+
+/* ------------------------ GAME ID -------------------------*/
+/// # Represent a stable identifier of a game
+/// Unlike the positional id of a game, which changes when a game is
+/// inserted before it in the database, the stable id is derived from the
+/// name of the game: it is the name in lower case, where any sequence of
+/// non alphanumeric characters is replaced by a dash.
+/// Games having the same name are told apart by a discriminator following
+/// two dashes (see `DataBase::get_game_stable_id`), which a name alone
+/// cannot give.
+/// It can be used in bookmarks or URLs.
+/// ```
+/// use pobsdlib::models::GameId;
+/// let id = GameId::from_name("Airships: Conquer the Skies");
+/// assert_eq!(id.as_str(), "airships-conquer-the-skies");
+/// assert_eq!("airships-conquer-the-skies".parse::<GameId>().unwrap(), id);
+/// assert!("Airships: Conquer the Skies".parse::<GameId>().is_err());
+/// ```
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct GameId(String);
+
+impl GameId {
+    /// Returns the stable id derived from the name of a game.
+    /// Names without any alphanumeric character give the id "game".
+    pub fn from_name(name: &str) -> Self {
+        let mut slug = String::new();
+        for c in name.chars() {
+            if c.is_alphanumeric() {
+                slug.extend(c.to_lowercase());
+            } else if !slug.is_empty() && !slug.ends_with('-') {
+                slug.push('-');
+            }
+        }
+        if slug.ends_with('-') {
+            slug.pop();
+        }
+        if slug.is_empty() {
+            slug.push_str("game");
+        }
+        GameId(slug)
+    }
+    /// Returns the id followed by two dashes and the discriminator, used
+    /// to tell apart games having the same name. The discriminator is
+    /// turned into an id the same way as a name.
+    pub fn with_discriminator(&self, discriminator: &str) -> Self {
+        GameId(format!("{}--{}", self.0, GameId::from_name(discriminator)))
+    }
+    /// Returns the id as a string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for GameId {
+    type Err = ParseError;
+    /// Parses a stable id as returned by `GameId::as_str`.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let is_name_id = |text: &str| !text.is_empty() && GameId::from_name(text).as_str() == text;
+        let valid = match text.split_once("--") {
+            Some((name, discriminator)) => is_name_id(name) && is_name_id(discriminator),
+            None => is_name_id(text),
+        };
+        if valid {
+            Ok(GameId(text.to_string()))
+        } else {
+            Err(ParseError::new(
+                ParseErrorKind::InvalidValue,
+                text,
+                "stable ids are made of lower case alphanumeric characters and dashes",
+            ))
+        }
+    }
+}
+
+impl fmt::Display for GameId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Serialize for GameId {
+    /// Serializes the id as a string.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for GameId {
+    /// Deserializes a string, checking that it is a valid stable id.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(de::Error::custom)
+    }
+}
+
+/// Parses the value of a typed field, an empty value giving None.
+/// The column of the error, if any, is set assuming the value follows
+/// the name of the field and a tab.
+fn parse_value<T: FromStr<Err = ParseError>>(
+    name: &str,
+    value: &str,
+) -> Result<Option<T>, ParseError> {
+    if value.is_empty() {
+        return Ok(None);
+    }
+    match value.parse() {
+        Ok(value) => Ok(Some(value)),
+        Err(error) => Err(error.with_column(name.chars().count() + 2)),
+    }
+}
+
+/* ------------------------ EXTRA FIELDS -------------------------*/
+/// # Store the fields of a game unknown to the library
+/// The fields are kept in the order they were added, so that they
+/// can be written back as they were read.
+/// ```
+/// use pobsdlib::models::ExtraFields;
+/// let mut extra = ExtraFields::new();
+/// extra.insert("Second", "2");
+/// extra.insert("First", "1");
+/// extra.insert("Second", "two");
+/// assert_eq!(extra.get("Second"), Some("two"));
+/// let keys: Vec<&str> = extra.iter().map(|(key, _)| key).collect();
+/// assert_eq!(keys, vec!["Second", "First"]);
+/// ```
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct ExtraFields {
+    entries: Vec<(String, String)>,
+}
+
+impl ExtraFields {
+    /// Is equivalent to ExtraFields::default().
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Returns the value of the field if it exists, None otherwise.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.as_str())
+    }
+    /// Sets the value of the field. A new field is added at the end,
+    /// an existing field keeps its position.
+    pub fn insert(&mut self, key: &str, value: &str) {
+        match self.entries.iter_mut().find(|(name, _)| name == key) {
+            Some(entry) => entry.1 = value.to_string(),
+            None => self.entries.push((key.to_string(), value.to_string())),
+        }
+    }
+    /// Removes the field and returns its value if it exists.
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let index = self.entries.iter().position(|(name, _)| name == key)?;
+        Some(self.entries.remove(index).1)
+    }
+    /// Returns an iterator over the (name, value) pairs, in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+    /// Returns the number of fields.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    /// Returns true if there is no field.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl Serialize for ExtraFields {
+    /// Serializes the fields as a map, in insertion order.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.entries.len()))?;
+        for (key, value) in &self.entries {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+struct ExtraFieldsVisitor;
+
+impl<'de> Visitor<'de> for ExtraFieldsVisitor {
+    type Value = ExtraFields;
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a map of field names to values")
+    }
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+        let mut extra = ExtraFields::new();
+        while let Some((key, value)) = access.next_entry::<String, String>()? {
+            extra.insert(&key, &value);
+        }
+        Ok(extra)
+    }
+}
+
+impl<'de> Deserialize<'de> for ExtraFields {
+    /// Deserializes a map, keeping the order of the fields.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(ExtraFieldsVisitor)
+    }
+}
+
 /* ------------------------ GAME -------------------------*/
 /// This trait is needed if you use ItemCollection with Game struct.
 /// Implies ItemTraits
+///
+/// Breaking change: `get_tags` and `get_genres` return slices instead of
+/// `&Vec<String>`, so that GameRef implements the trait without copying
+/// its values. An implementation returning `&Vec<String>` must return
+/// `&[String]` instead (with `#[allow(refining_impl_trait)]`), which needs
+/// Rust 1.75. `get_field` is provided, so existing implementations do not
+/// have to add it, but their fields are then unknown to the queries.
 pub trait GameTraits: ItemTraits {
-    fn get_tags(&self) -> &Vec<String>;
-    fn get_genres(&self) -> &Vec<String>;
-    fn field_contains(&self, field_name: &str, field_value: &str) -> bool;
+    fn get_tags(&self) -> &[impl AsRef<str>];
+    fn get_genres(&self) -> &[impl AsRef<str>];
+    /// Returns the field corresponding to the name. Returns an
+    /// UnknownField error by default, implement it to query the fields.
+    fn get_field(&self, name: &str) -> Result<Field<'_>, ParseError> {
+        Err(ParseError::new(
+            ParseErrorKind::UnknownField,
+            name,
+            &unknown_field_hint(name),
+        ))
+    }
+    /// Returns true if the field contains the value (not case sensitive).
+    /// Fields with several values (e.g. Tags) match if one of the values
+    /// contains the value. Returns false if the field is unknown.
+    fn field_contains(&self, field_name: &str, field_value: &str) -> bool {
+        match Pattern::new(field_value, MatchMode::Substring) {
+            Ok(pattern) => self.field_matches(field_name, &pattern),
+            Err(_) => false,
+        }
+    }
+    /// Returns true if the field matches the pattern (see `matching`).
+    /// Fields with several values (e.g. Tags) match if one of the values
+    /// matches. Returns false if the field is unknown.
+    fn field_matches(&self, field_name: &str, pattern: &Pattern) -> bool {
+        match self.get_field(field_name) {
+            Ok(field) => field.values().iter().any(|value| pattern.is_match(value)),
+            Err(_) => false,
+        }
+    }
 }
 
 /// This trait is needed if you use ItemCollection wit a mutable Game struct.
 /// Implies ItemTraitsMut
 pub trait GameTraitsMut: ItemTraitsMut {
-    fn update(&mut self, field: Field);
+    fn update(&mut self, field: Field) -> Result<(), ParseError>;
 }
 
 /// # Represent a game
@@ -232,7 +733,7 @@ pub trait GameTraitsMut: ItemTraitsMut {
 /// let mut game = Game::new();
 /// // the update method takes a Field enum and update the Game fields accordingly
 /// for line in database.lines() {
-///     game.update(Field::from(line));
+///     game.update(Field::from(line).unwrap()).unwrap();
 /// }
 /// assert_eq!(game.name,"AaaaaAAaaaAAAaaAAAAaAAAAA!!! for the Awesome");
 /// assert_eq!(game.cover,"AaaaaA_for_the_Awesome_Cover.jpg");
@@ -249,11 +750,15 @@ pub trait GameTraitsMut: ItemTraitsMut {
 /// assert_eq!(game.version,"");
 /// assert_eq!(game.status,"");
 /// // you also can use the get_field method to get the corresponding Field enum
-/// assert_eq!(game.get_field("Year"), Field::SingleItem("Year","2011"));
+/// assert_eq!(game.get_field("Year").unwrap(), Field::SingleItem("Year","2011"));
 /// // get_field is not case sensitive
-/// assert_eq!(game.get_field("yEaR"), Field::SingleItem("Year","2011"));
+/// assert_eq!(game.get_field("yEaR").unwrap(), Field::SingleItem("Year","2011"));
+/// // and returns an error for unknown fields
+/// assert!(game.get_field("Unknown").is_err());
 /// ```
-#[derive(Serialize, Default, PartialEq)]
+#[allow(clippy::tabs_in_doc_comments)]
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Debug)]
+#[serde(default)]
 pub struct Game {
     /// The id of the game.
     pub id: usize,
@@ -285,6 +790,14 @@ pub struct Game {
     pub version: String,
     /// When tested on -current.
     pub status: String,
+    /// When the game was added to the database.
+    pub added: Option<Date>,
+    /// When the game was last updated in the database.
+    pub updated: Option<Date>,
+    /// The id of the game in the IGDB database.
+    pub igdb_id: Option<IgdbId>,
+    /// Fields unknown to the library, in the order they were read.
+    pub extra: ExtraFields,
 }
 
 impl Game {
@@ -293,9 +806,19 @@ impl Game {
         Self::default()
     }
     /// Given a field name, return the corresponding Field enum
-    /// It is not case sensitive
-    pub fn get_field(&self, name: &str) -> Field {
-        match name.to_lowercase().as_str() {
+    /// It is not case sensitive. Fields unknown to the library are looked
+    /// up in the extra fields (case sensitive), a ParseError is returned
+    /// if the field does not exist.
+    /// ```
+    /// use pobsdlib::models::{Field, Game};
+    /// let mut game = Game::new();
+    /// game.extra.insert("NewField", "value");
+    /// assert_eq!(game.get_field("NewField").unwrap(), Field::Extra("NewField", "value"));
+    /// assert!(game.get_field("OtherField").is_err());
+    /// ```
+    pub fn get_field(&self, name: &str) -> Result<Field<'_>, ParseError> {
+        let field = match name.to_lowercase().as_str() {
+            "game" => Field::NewGame(&self.name),
             "cover" => Field::SingleItem("Cover", &self.cover),
             "engine" => Field::SingleItem("Engine", &self.engine),
             "setup" => Field::SingleItem("Setup", &self.setup),
@@ -306,6 +829,13 @@ impl Game {
             "pub" => Field::SingleItem("Pub", &self.publi),
             "version" => Field::SingleItem("Version", &self.version),
             "status" => Field::SingleItem("Status", &self.status),
+            "added" => Field::SingleItem("Added", self.added.as_ref().map_or("", Date::as_str)),
+            "updated" => {
+                Field::SingleItem("Updated", self.updated.as_ref().map_or("", Date::as_str))
+            }
+            "igdbid" => {
+                Field::SingleItem("IgdbId", self.igdb_id.as_ref().map_or("", IgdbId::as_str))
+            }
             "store" => {
                 let mut stores: Vec<&str> = Vec::new();
                 for store in &self.store {
@@ -327,8 +857,53 @@ impl Game {
                 }
                 Field::MultipleItems("Tags", tags)
             }
-            _ => panic!("Unkown filed {}", name),
+            _ => match self.extra.iter().find(|(key, _)| *key == name) {
+                Some((key, value)) => Field::Extra(key, value),
+                None => {
+                    return Err(ParseError::new(
+                        ParseErrorKind::UnknownField,
+                        name,
+                        &unknown_field_hint(name),
+                    ))
+                }
+            },
+        };
+        Ok(field)
+    }
+}
+
+impl fmt::Display for Game {
+    /// Writes the game as a block of lines of the database,
+    /// the fields being written in the database order.
+    /// The Added, Updated and IgdbId fields, only present in the newer
+    /// versions of the database, are written if at least one of them is set.
+    /// ```
+    /// use pobsdlib::models::Game;
+    /// let mut game = Game::new();
+    /// game.name = "Toto".to_string();
+    /// game.engine = "FNA".to_string();
+    /// game.tags = vec!["tag1".to_string(), "tag2".to_string()];
+    /// assert_eq!(
+    ///     game.to_string(),
+    ///     "Game\tToto\nCover\nEngine\tFNA\nSetup\nRuntime\nStore\nHints\nGenre\n\
+    ///      Tags\ttag1, tag2\nYear\nDev\nPub\nVersion\nStatus\n"
+    /// );
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", Field::NewGame(&self.name).as_line())?;
+        let dated = self.added.is_some() || self.updated.is_some() || self.igdb_id.is_some();
+        for name in FIELD_NAMES.iter().skip(1) {
+            if !dated && DATED_FIELD_NAMES.contains(name) {
+                continue;
+            }
+            if let Ok(field) = self.get_field(name) {
+                writeln!(f, "{}", field.as_line())?;
+            }
+        }
+        for (key, value) in self.extra.iter() {
+            writeln!(f, "{}", Field::Extra(key, value).as_line())?;
         }
+        Ok(())
     }
 }
 
@@ -337,6 +912,10 @@ impl ItemTraits for Game {
     fn get_name(&self) -> &str {
         &self.name
     }
+    /// Returns the id of the game.
+    fn get_id(&self) -> usize {
+        self.id
+    }
 }
 
 impl ItemTraits for &Game {
@@ -344,6 +923,10 @@ impl ItemTraits for &Game {
     fn get_name(&self) -> &str {
         &self.name
     }
+    /// Returns the id of the game.
+    fn get_id(&self) -> usize {
+        self.id
+    }
 }
 
 impl ItemTraits for &mut Game {
@@ -351,6 +934,10 @@ impl ItemTraits for &mut Game {
     fn get_name(&self) -> &str {
         &self.name
     }
+    /// Returns the id of the game.
+    fn get_id(&self) -> usize {
+        self.id
+    }
 }
 
 impl ItemTraitsMut for Game {
@@ -373,15 +960,18 @@ impl GameTraitsMut for Game {
     /// use pobsdlib::models::{Field,Game,GameTraitsMut};
     ///
     /// let line_str = "Game\tName of the game";
-    /// let field = Field::from(line_str);
+    /// let field = Field::from(line_str).unwrap();
     /// let mut game = Game::new();
-    /// game.update(field);
+    /// game.update(field).unwrap();
     /// assert_eq!(game.name,"Name of the game");
     /// ```
     /// The id cannot be set this way and the `set_id` method must be used.
-    fn update(&mut self, field: Field) {
+    /// Field::Extra are stored in the extra fields.
+    /// Returns a ParseError if the field is unknown.
+    fn update(&mut self, field: Field) -> Result<(), ParseError> {
         match field {
             Field::NewGame(name) => self.name = name.to_string(),
+            Field::Extra(left, right) => self.extra.insert(left, right),
             Field::SingleItem(left, right) => {
                 match left {
                     "Cover" => self.cover = right.to_string(),
@@ -394,7 +984,16 @@ impl GameTraitsMut for Game {
                     "Pub" => self.publi = right.to_string(),
                     "Version" => self.version = right.to_string(),
                     "Status" => self.status = right.to_string(),
-                    _ => panic!("unknown single item field: unable to set"),
+                    "Added" => self.added = parse_value(left, right)?,
+                    "Updated" => self.updated = parse_value(left, right)?,
+                    "IgdbId" => self.igdb_id = parse_value(left, right)?,
+                    _ => {
+                        return Err(ParseError::new(
+                            ParseErrorKind::UnknownField,
+                            left,
+                            &unknown_field_hint(left),
+                        ))
+                    }
                 };
             }
             Field::MultipleItems(left, right) => {
@@ -420,110 +1019,385 @@ impl GameTraitsMut for Game {
                         }
                         self.genres = genres;
                     }
-                    _ => panic!("unknown multiple item field: unable to set"),
+                    _ => {
+                        return Err(ParseError::new(
+                            ParseErrorKind::UnknownField,
+                            left,
+                            &unknown_field_hint(left),
+                        ))
+                    }
                 };
             }
         };
+        Ok(())
     }
 }
 impl GameTraits for Game {
     /// Returns the tag vector of the game.
-    fn get_tags(&self) -> &Vec<String> {
+    #[allow(refining_impl_trait)]
+    fn get_tags(&self) -> &[String] {
         &self.tags
     }
+    /// Returns the genre vector of the game.
+    #[allow(refining_impl_trait)]
+    fn get_genres(&self) -> &[String] {
+        &self.genres
+    }
+    /// See `Game::get_field`.
+    fn get_field(&self, name: &str) -> Result<Field<'_>, ParseError> {
+        Game::get_field(self, name)
+    }
+}
+
+impl GameTraits for &Game {
     /// Returns the tag vector of the game.
-    fn get_genres(&self) -> &Vec<String> {
+    #[allow(refining_impl_trait)]
+    fn get_tags(&self) -> &[String] {
+        &self.tags
+    }
+    /// Returns the genre vector of the game.
+    #[allow(refining_impl_trait)]
+    fn get_genres(&self) -> &[String] {
         &self.genres
     }
-    fn field_contains(&self, field_name: &str, field_value: &str) -> bool {
-        match self.get_field(field_name) {
-            Field::NewGame(value) => value.to_lowercase().contains(&field_value.to_lowercase()),
-            Field::SingleItem(_, value) => {
-                value.to_lowercase().contains(&field_value.to_lowercase())
+    /// See `Game::get_field`.
+    fn get_field(&self, name: &str) -> Result<Field<'_>, ParseError> {
+        Game::get_field(self, name)
+    }
+}
+
+/* ------------------------ GAME REF -------------------------*/
+/// # Represent a game borrowing its values from the database text
+/// Unlike Game, a GameRef does not copy the values of the fields: they
+/// are slices of the text of the database (see `DataBaseBuffer`).
+/// The Added, Updated and IgdbId fields are checked when parsed but are
+/// kept as written in the database.
+/// ```
+/// use pobsdlib::models::{Field, GameRef, GameTraits};
+/// let database = "Game\tToto\nEngine\tFNA\nTags\ttag1, tag2\nIgdbId\t1234";
+/// let mut game = GameRef::new();
+/// for line in database.lines() {
+///     game.update(Field::from(line).unwrap()).unwrap();
+/// }
+/// assert_eq!(game.name, "Toto");
+/// assert_eq!(game.engine, "FNA");
+/// assert_eq!(game.get_tags(), vec!["tag1", "tag2"]);
+/// assert!(game.field_contains("IgdbId", "1234"));
+/// let game = game.to_game().unwrap();
+/// assert_eq!(game.igdb_id.unwrap().id(), 1234);
+/// ```
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct GameRef<'a> {
+    /// The id of the game.
+    pub id: usize,
+    /// The name of the game.
+    pub name: &'a str,
+    /// The cover of the game.
+    pub cover: &'a str,
+    /// The engine used by the game.
+    pub engine: &'a str,
+    /// Step(s) to setup the game.
+    pub setup: &'a str,
+    /// The executable in the package.
+    pub runtime: &'a str,
+    /// A vector with store urls.
+    pub store: Vec<&'a str>,
+    /// Hints (as the name imply).
+    pub hints: &'a str,
+    /// A vector of genres associated with the game.
+    pub genres: Vec<&'a str>,
+    /// A vector of tags associated with the game.
+    pub tags: Vec<&'a str>,
+    /// Released year.
+    pub year: &'a str,
+    /// Developer (as the name imply).
+    pub dev: &'a str,
+    /// Publisher.
+    pub publi: &'a str,
+    /// Version of the game.
+    pub version: &'a str,
+    /// When tested on -current.
+    pub status: &'a str,
+    /// When the game was added to the database (YYYY-MM-DD).
+    pub added: &'a str,
+    /// When the game was last updated in the database (YYYY-MM-DD).
+    pub updated: &'a str,
+    /// The id of the game in the IGDB database.
+    pub igdb_id: &'a str,
+    /// Fields unknown to the library, in the order they were read.
+    pub extra: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> GameRef<'a> {
+    /// Is equivalent to GameRef::default().
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Sets one attribute of the game according to the Field enum given
+    /// (see `GameTraitsMut::update`).
+    /// Returns a ParseError if the field is unknown or if its value is invalid.
+    pub fn update(&mut self, field: Field<'a>) -> Result<(), ParseError> {
+        match field {
+            Field::NewGame(name) => self.name = name,
+            Field::Extra(left, right) => {
+                match self.extra.iter_mut().find(|(key, _)| *key == left) {
+                    Some(entry) => entry.1 = right,
+                    None => self.extra.push((left, right)),
+                };
             }
-            Field::MultipleItems(_, value) => value
-                .join("--")
-                .to_lowercase()
-                .contains(&field_value.to_lowercase()),
+            Field::SingleItem(left, right) => {
+                match left {
+                    "Cover" => self.cover = right,
+                    "Engine" => self.engine = right,
+                    "Setup" => self.setup = right,
+                    "Runtime" => self.runtime = right,
+                    "Hints" => self.hints = right,
+                    "Year" => self.year = right,
+                    "Dev" => self.dev = right,
+                    "Pub" => self.publi = right,
+                    "Version" => self.version = right,
+                    "Status" => self.status = right,
+                    "Added" => {
+                        parse_value::<Date>(left, right)?;
+                        self.added = right;
+                    }
+                    "Updated" => {
+                        parse_value::<Date>(left, right)?;
+                        self.updated = right;
+                    }
+                    "IgdbId" => {
+                        parse_value::<IgdbId>(left, right)?;
+                        self.igdb_id = right;
+                    }
+                    _ => {
+                        return Err(ParseError::new(
+                            ParseErrorKind::UnknownField,
+                            left,
+                            &unknown_field_hint(left),
+                        ))
+                    }
+                };
+            }
+            Field::MultipleItems(left, right) => {
+                match left {
+                    "Store" => self.store = right,
+                    "Tags" => self.tags = right,
+                    "Genre" => self.genres = right,
+                    _ => {
+                        return Err(ParseError::new(
+                            ParseErrorKind::UnknownField,
+                            left,
+                            &unknown_field_hint(left),
+                        ))
+                    }
+                };
+            }
+        };
+        Ok(())
+    }
+    /// Given a field name, return the corresponding Field enum
+    /// (see `Game::get_field`).
+    pub fn get_field(&self, name: &str) -> Result<Field<'a>, ParseError> {
+        let field = match name.to_lowercase().as_str() {
+            "game" => Field::NewGame(self.name),
+            "cover" => Field::SingleItem("Cover", self.cover),
+            "engine" => Field::SingleItem("Engine", self.engine),
+            "setup" => Field::SingleItem("Setup", self.setup),
+            "runtime" => Field::SingleItem("Runtime", self.runtime),
+            "hints" => Field::SingleItem("Hints", self.hints),
+            "year" => Field::SingleItem("Year", self.year),
+            "dev" => Field::SingleItem("Dev", self.dev),
+            "pub" => Field::SingleItem("Pub", self.publi),
+            "version" => Field::SingleItem("Version", self.version),
+            "status" => Field::SingleItem("Status", self.status),
+            "added" => Field::SingleItem("Added", self.added),
+            "updated" => Field::SingleItem("Updated", self.updated),
+            "igdbid" => Field::SingleItem("IgdbId", self.igdb_id),
+            "store" => Field::MultipleItems("Store", self.store.clone()),
+            "genre" => Field::MultipleItems("Genre", self.genres.clone()),
+            "tags" => Field::MultipleItems("Tags", self.tags.clone()),
+            _ => match self.extra.iter().find(|(key, _)| *key == name) {
+                Some((key, value)) => Field::Extra(key, value),
+                None => {
+                    return Err(ParseError::new(
+                        ParseErrorKind::UnknownField,
+                        name,
+                        &unknown_field_hint(name),
+                    ))
+                }
+            },
+        };
+        Ok(field)
+    }
+    /// Returns an owned copy of the game.
+    /// Returns a ParseError if a typed field (e.g. Added) is invalid.
+    pub fn to_game(&self) -> Result<Game, ParseError> {
+        let mut game = Game::new();
+        game.set_id(self.id);
+        game.update(Field::NewGame(self.name))?;
+        for name in FIELD_NAMES.iter().skip(1) {
+            game.update(self.get_field(name)?)?;
+        }
+        for (key, value) in &self.extra {
+            game.update(Field::Extra(key, value))?;
         }
+        Ok(game)
     }
 }
 
-impl GameTraits for &Game {
+impl<'a> ItemTraits for GameRef<'a> {
+    /// Returns the name of the game.
+    fn get_name(&self) -> &str {
+        self.name
+    }
+    /// Returns the id of the game.
+    fn get_id(&self) -> usize {
+        self.id
+    }
+}
+
+impl<'a> ItemTraits for &GameRef<'a> {
+    /// Returns the name of the game.
+    fn get_name(&self) -> &str {
+        self.name
+    }
+    /// Returns the id of the game.
+    fn get_id(&self) -> usize {
+        self.id
+    }
+}
+
+impl<'a> ItemTraitsMut for GameRef<'a> {
+    /// Sets the id of the game.
+    fn set_id(&mut self, id: usize) {
+        self.id = id;
+    }
+}
+
+impl<'a> GameTraits for GameRef<'a> {
     /// Returns the tag vector of the game.
-    fn get_tags(&self) -> &Vec<String> {
+    #[allow(refining_impl_trait)]
+    fn get_tags(&self) -> &[&'a str] {
         &self.tags
     }
+    /// Returns the genre vector of the game.
+    #[allow(refining_impl_trait)]
+    fn get_genres(&self) -> &[&'a str] {
+        &self.genres
+    }
+    /// See `GameRef::get_field`.
+    fn get_field(&self, name: &str) -> Result<Field<'_>, ParseError> {
+        GameRef::get_field(self, name)
+    }
+}
+
+impl<'a> GameTraits for &GameRef<'a> {
     /// Returns the tag vector of the game.
-    fn get_genres(&self) -> &Vec<String> {
+    #[allow(refining_impl_trait)]
+    fn get_tags(&self) -> &[&'a str] {
+        &self.tags
+    }
+    /// Returns the genre vector of the game.
+    #[allow(refining_impl_trait)]
+    fn get_genres(&self) -> &[&'a str] {
         &self.genres
     }
-    fn field_contains(&self, field_name: &str, field_value: &str) -> bool {
-        match self.get_field(field_name) {
-            Field::NewGame(value) => value.to_lowercase().contains(&field_value.to_lowercase()),
-            Field::SingleItem(_, value) => {
-                value.to_lowercase().contains(&field_value.to_lowercase())
-            }
-            Field::MultipleItems(_, value) => value
-                .join("--")
-                .to_lowercase()
-                .contains(&field_value.to_lowercase()),
-        }
+    /// See `GameRef::get_field`.
+    fn get_field(&self, name: &str) -> Result<Field<'_>, ParseError> {
+        GameRef::get_field(self, name)
     }
 }
 
 /* ------------------------- TESTS --------------------------*/
 
 #[cfg(test)]
+#[allow(clippy::needless_borrow)]
 mod test_field_methods {
     use super::*;
     #[test]
     fn as_line_game() {
         let input = "Game\tToto";
-        let field = Field::from(&input);
+        let field = Field::from(&input).unwrap();
         assert_eq!(field.as_line(), input.to_string());
     }
     #[test]
     fn as_line_engine() {
         let input = "Engine\tToto";
-        let field = Field::from(&input);
+        let field = Field::from(&input).unwrap();
         assert_eq!(field.as_line(), input.to_string());
     }
     #[test]
     fn as_line_tags() {
         let input = "Tags\ttag1, tag2";
-        let field = Field::from(&input);
+        let field = Field::from(&input).unwrap();
         assert_eq!(field.as_line(), input.to_string());
     }
     #[test]
     fn as_line_stores() {
         let input = "Tags\turl1 url2";
-        let field = Field::from(&input);
+        let field = Field::from(&input).unwrap();
+        assert_eq!(field.as_line(), input.to_string());
+    }
+    #[test]
+    fn as_line_empty() {
+        let input = "Tags";
+        let field = Field::from(input).unwrap();
+        assert_eq!(field.as_line(), input.to_string());
+        let field = Field::MultipleItems("Store", Vec::new());
+        assert_eq!(field.as_line(), "Store".to_string());
+    }
+    #[test]
+    fn as_line_extra() {
+        let input = "NewField\tValue";
+        let field = Field::from(input).unwrap();
         assert_eq!(field.as_line(), input.to_string());
     }
     #[test]
+    fn from_extra_line() {
+        let input = "Players\t1-4";
+        let field = Field::from(input).unwrap();
+        assert!(Field::Extra("Players", "1-4") == field);
+        assert!(Field::from("IgdbId2\t1234").is_err());
+        assert!(Field::from("New Field\t1234").is_err());
+        assert!(Field::from("newField\t1234").is_err());
+        assert!(Field::from("\t1234").is_err());
+    }
+    #[test]
     fn from_game_line() {
         let input = "Game\tToto";
-        let field = Field::from(&input);
+        let field = Field::from(&input).unwrap();
         assert!(Field::NewGame(&"Toto") == field);
     }
     #[test]
     fn from_single_line() {
         let input = "Cover\tToto";
-        let field = Field::from(&input);
+        let field = Field::from(&input).unwrap();
         assert!(Field::SingleItem(&"Cover", &"Toto") == field);
     }
     #[test]
     fn from_mutilple_line() {
         let input = "Genre\tfirst, second";
-        let field = Field::from(&input);
+        let field = Field::from(&input).unwrap();
         assert!(Field::MultipleItems(&"Genre", vec![&"first", &"second"]) == field);
     }
     #[test]
-    #[should_panic]
     fn from_malformed_line() {
         let input = "Let's panic";
-        Field::from(&input);
+        let error = Field::from(&input).unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::UnknownField);
+        assert_eq!(error.key, "Let's panic");
+        assert_eq!(error.column, Some(1));
+    }
+    #[test]
+    fn from_unknown_field_hint() {
+        let error = Field::from("genre\tRPG").unwrap_err();
+        assert_eq!(error.hint, "did you mean Genre?");
+        // capitalized names are extra fields, only querying them can fail
+        assert!(Field::from("Toto\tRPG").is_ok());
+        let error = Game::new().get_field("Toto").unwrap_err();
+        assert!(error.hint.starts_with("expected one of Game, Cover"));
+        let error = Field::from("Tag\tRPG").unwrap_err();
+        assert_eq!(error.hint, "did you mean Tags?");
     }
 }
 
@@ -559,6 +1433,7 @@ mod test_item_methods {
 }
 
 #[cfg(test)]
+#[allow(clippy::needless_borrow, clippy::op_ref)]
 mod test_game_methods {
     use super::*;
     #[test]
@@ -571,16 +1446,22 @@ mod test_game_methods {
     fn get_engine() {
         let mut game = Game::new();
         let field = Field::SingleItem(&"Engine", &"Test");
-        game.update(field);
-        let field = game.get_field("Engine");
+        game.update(field).unwrap();
+        let field = game.get_field("Engine").unwrap();
         assert_eq!(Field::SingleItem(&"Engine", &"Test"), field);
     }
     #[test]
+    fn get_game_field() {
+        let mut game = Game::new();
+        game.update(Field::NewGame("Test")).unwrap();
+        assert_eq!(game.get_field("game").unwrap(), Field::NewGame("Test"));
+    }
+    #[test]
     fn get_store() {
         let mut game = Game::new();
         let field = Field::MultipleItems(&"Store", vec![&"ST1", &"ST2"]);
-        game.update(field);
-        let field = game.get_field("Store");
+        game.update(field).unwrap();
+        let field = game.get_field("Store").unwrap();
         assert_eq!(Field::MultipleItems(&"Store", vec![&"ST1", &"ST2"]), field);
     }
     #[test]
@@ -613,112 +1494,358 @@ mod test_game_methods {
     fn update_from_name() {
         let mut game = Game::new();
         let field = Field::NewGame(&"Test");
-        game.update(field);
+        game.update(field).unwrap();
         assert_eq!(game.name, "Test".to_string());
     }
     #[test]
     fn update_from_cover() {
         let mut game = Game::new();
         let field = Field::SingleItem(&"Cover", &"Test");
-        game.update(field);
+        game.update(field).unwrap();
         assert_eq!(game.cover, "Test".to_string());
     }
     #[test]
     fn update_from_engine() {
         let mut game = Game::new();
         let field = Field::SingleItem(&"Engine", &"Test");
-        game.update(field);
+        game.update(field).unwrap();
         assert_eq!(game.engine, "Test".to_string());
     }
     #[test]
     fn update_from_setup() {
         let mut game = Game::new();
         let field = Field::SingleItem(&"Setup", &"Test");
-        game.update(field);
+        game.update(field).unwrap();
         assert_eq!(game.setup, "Test".to_string());
     }
     #[test]
     fn update_from_runtime() {
         let mut game = Game::new();
         let field = Field::SingleItem(&"Runtime", &"Test");
-        game.update(field);
+        game.update(field).unwrap();
         assert_eq!(game.runtime, "Test".to_string());
     }
     #[test]
     fn update_from_hints() {
         let mut game = Game::new();
         let field = Field::SingleItem(&"Hints", &"Test");
-        game.update(field);
+        game.update(field).unwrap();
         assert_eq!(game.hints, "Test".to_string());
     }
     #[test]
     fn update_from_year() {
         let mut game = Game::new();
         let field = Field::SingleItem(&"Year", &"Test");
-        game.update(field);
+        game.update(field).unwrap();
         assert_eq!(game.year, "Test".to_string());
     }
     #[test]
     fn update_from_dev() {
         let mut game = Game::new();
         let field = Field::SingleItem(&"Dev", &"Test");
-        game.update(field);
+        game.update(field).unwrap();
         assert_eq!(game.dev, "Test".to_string());
     }
     #[test]
     fn update_from_publi() {
         let mut game = Game::new();
         let field = Field::SingleItem(&"Pub", &"Test");
-        game.update(field);
+        game.update(field).unwrap();
         assert_eq!(game.publi, "Test".to_string());
     }
     #[test]
     fn update_from_version() {
         let mut game = Game::new();
         let field = Field::SingleItem(&"Version", &"Test");
-        game.update(field);
+        game.update(field).unwrap();
         assert_eq!(game.version, "Test".to_string());
     }
     #[test]
     fn update_from_status() {
         let mut game = Game::new();
         let field = Field::SingleItem(&"Status", &"Test");
-        game.update(field);
+        game.update(field).unwrap();
         assert_eq!(game.status, "Test".to_string());
     }
     #[test]
-    #[should_panic]
     fn update_from_malformed_singleitemfield() {
         let mut game = Game::new();
         let field = Field::SingleItem(&"Panic", &"Test");
-        game.update(field);
+        let error = game.update(field).unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::UnknownField);
+        assert_eq!(error.key, "Panic");
+    }
+    #[test]
+    fn update_from_added() {
+        let mut game = Game::new();
+        let field = Field::SingleItem("Added", "2022-01-31");
+        game.update(field).unwrap();
+        assert_eq!(game.added, Date::new(2022, 1, 31));
+        let field = game.get_field("added").unwrap();
+        assert_eq!(Field::SingleItem("Added", "2022-01-31"), field);
+    }
+    #[test]
+    fn update_from_updated() {
+        let mut game = Game::new();
+        let field = Field::SingleItem("Updated", "2023-12-01");
+        game.update(field).unwrap();
+        assert_eq!(game.updated, Date::new(2023, 12, 1));
+        game.update(Field::SingleItem("Updated", "")).unwrap();
+        assert_eq!(game.updated, None);
+    }
+    #[test]
+    fn update_from_igdb_id() {
+        let mut game = Game::new();
+        let field = Field::SingleItem("IgdbId", "1234");
+        game.update(field).unwrap();
+        assert_eq!(game.igdb_id.as_ref().map(IgdbId::id), Some(1234));
+        let field = game.get_field("IgdbId").unwrap();
+        assert_eq!(Field::SingleItem("IgdbId", "1234"), field);
+    }
+    #[test]
+    fn update_from_invalid_date() {
+        let mut game = Game::new();
+        let field = Field::SingleItem("Added", "2022-13-01");
+        let error = game.update(field).unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::InvalidValue);
+        assert_eq!(error.key, "2022-13-01");
+        assert_eq!(error.column, Some(7));
+        assert_eq!(game.added, None);
+    }
+    #[test]
+    fn display_dated_fields() {
+        let mut game = Game::new();
+        game.name = "Toto".to_string();
+        assert!(game.to_string().ends_with("Status\n"));
+        game.added = Date::new(2022, 1, 1);
+        assert!(game
+            .to_string()
+            .ends_with("Status\nAdded\t2022-01-01\nUpdated\nIgdbId\n"));
     }
     #[test]
     fn update_from_store() {
         let mut game = Game::new();
         let field = Field::MultipleItems(&"Store", vec![&"ST1", &"ST2"]);
-        game.update(field);
+        game.update(field).unwrap();
         assert_eq!(game.store, vec!["ST1".to_string(), "ST2".to_string()]);
     }
     #[test]
     fn update_from_tags() {
         let mut game = Game::new();
         let field = Field::MultipleItems(&"Tags", vec![&"Tag1", &"Tag2"]);
-        game.update(field);
+        game.update(field).unwrap();
         assert_eq!(game.tags, vec!["Tag1".to_string(), "Tag2".to_string()]);
     }
     #[test]
     fn update_from_genres() {
         let mut game = Game::new();
         let field = Field::MultipleItems(&"Genre", vec![&"Ge1", &"Ge2"]);
-        game.update(field);
+        game.update(field).unwrap();
         assert_eq!(game.genres, vec!["Ge1".to_string(), "Ge2".to_string()]);
     }
     #[test]
-    #[should_panic]
     fn update_from_malformed_multipleitemsfield() {
         let mut game = Game::new();
         let field = Field::MultipleItems(&"Panic", vec![&"Ge1", &"Ge2"]);
-        game.update(field);
+        let error = game.update(field).unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::UnknownField);
+        assert_eq!(error.key, "Panic");
+    }
+    #[test]
+    fn update_from_extra() {
+        let mut game = Game::new();
+        game.update(Field::Extra("Second", "2")).unwrap();
+        game.update(Field::Extra("First", "1")).unwrap();
+        assert_eq!(game.extra.get("First"), Some("1"));
+        assert_eq!(
+            game.get_field("Second").unwrap(),
+            Field::Extra("Second", "2")
+        );
+        assert!(game.field_contains("First", "1"));
+        assert!(game.to_string().ends_with("Status\nSecond\t2\nFirst\t1\n"));
+    }
+    #[test]
+    fn get_unknown_field() {
+        let game = Game::new();
+        let error = game.get_field("Panic").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::UnknownField);
+        assert_eq!(error.key, "Panic");
+        assert!(!game.field_contains("Panic", "Test"));
+    }
+    #[test]
+    fn field_matches_each_value() {
+        let mut game = Game::new();
+        game.update(Field::MultipleItems("Tags", vec!["nonindie", "shooter"]))
+            .unwrap();
+        assert!(!game.field_contains("Tags", "e--s"));
+        assert!(game.field_contains("Tags", "SHOOT"));
+        let pattern = Pattern::new("indie", MatchMode::Exact).unwrap();
+        assert!(!game.field_matches("Tags", &pattern));
+        let pattern = Pattern::new("^shoo", MatchMode::Regex).unwrap();
+        assert!(game.field_matches("Tags", &pattern));
+    }
+}
+
+#[cfg(test)]
+mod test_game_traits {
+    use super::*;
+    /// An implementation written before `get_field` was added.
+    struct Minimal {
+        tags: Vec<String>,
+    }
+    impl ItemTraits for Minimal {
+        fn get_name(&self) -> &str {
+            "Minimal"
+        }
+    }
+    impl GameTraits for Minimal {
+        #[allow(refining_impl_trait)]
+        fn get_tags(&self) -> &[String] {
+            &self.tags
+        }
+        #[allow(refining_impl_trait)]
+        fn get_genres(&self) -> &[String] {
+            &self.tags
+        }
+    }
+    #[test]
+    fn default_get_field() {
+        let game = Minimal {
+            tags: vec!["indie".to_string()],
+        };
+        assert_eq!(game.get_tags()[0], "indie");
+        let error = game.get_field("Tags").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::UnknownField);
+        assert!(!game.field_contains("Tags", "indie"));
+    }
+}
+
+#[cfg(test)]
+mod test_game_ref_methods {
+    use super::*;
+    #[test]
+    fn update_and_get_field() {
+        let mut game = GameRef::new();
+        game.update(Field::SingleItem("Engine", "Test")).unwrap();
+        game.update(Field::MultipleItems("Store", vec!["ST1", "ST2"]))
+            .unwrap();
+        game.update(Field::Extra("NewField", "Value")).unwrap();
+        assert_eq!(
+            game.get_field("Engine").unwrap(),
+            Field::SingleItem("Engine", "Test")
+        );
+        assert_eq!(
+            game.get_field("Store").unwrap(),
+            Field::MultipleItems("Store", vec!["ST1", "ST2"])
+        );
+        assert_eq!(
+            game.get_field("NewField").unwrap(),
+            Field::Extra("NewField", "Value")
+        );
+        assert!(game.get_field("Panic").is_err());
+    }
+    #[test]
+    fn update_from_invalid_date() {
+        let mut game = GameRef::new();
+        let error = game
+            .update(Field::SingleItem("Updated", "yesterday"))
+            .unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::InvalidValue);
+        assert_eq!(game.updated, "");
+    }
+    #[test]
+    fn to_game() {
+        let mut game_ref = GameRef::new();
+        game_ref.set_id(3);
+        game_ref.update(Field::NewGame("Toto")).unwrap();
+        game_ref
+            .update(Field::MultipleItems("Genre", vec!["Ge1", "Ge2"]))
+            .unwrap();
+        game_ref
+            .update(Field::SingleItem("Added", "2020-02-02"))
+            .unwrap();
+        let game = game_ref.to_game().unwrap();
+        assert_eq!(game.id, 3);
+        assert_eq!(game.name, "Toto".to_string());
+        assert_eq!(game.genres, vec!["Ge1".to_string(), "Ge2".to_string()]);
+        assert_eq!(game.added, Date::new(2020, 2, 2));
+    }
+}
+
+#[cfg(test)]
+mod test_deserialize {
+    use super::*;
+    use serde_json;
+    #[test]
+    fn date() {
+        let date: Date = serde_json::from_str("\"2022-02-28\"").unwrap();
+        assert_eq!(date, Date::new(2022, 2, 28).unwrap());
+        assert!(serde_json::from_str::<Date>("\"2022-02-29\"").is_err());
+    }
+    #[test]
+    fn igdb_id() {
+        let id: IgdbId = serde_json::from_str("1234").unwrap();
+        assert_eq!(id.as_str(), "1234");
+    }
+    #[test]
+    fn extra_fields_keep_order() {
+        let extra: ExtraFields = serde_json::from_str(r#"{"Second":"2","First":"1"}"#).unwrap();
+        let keys: Vec<&str> = extra.iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, vec!["Second", "First"]);
+    }
+    #[test]
+    fn game_missing_fields() {
+        let game: Game = serde_json::from_str(r#"{"id":1,"name":"Toto","tags":["tag1"]}"#).unwrap();
+        assert_eq!(game.name, "Toto");
+        assert_eq!(game.get_tags(), vec!["tag1"]);
+        assert_eq!(game.added, None);
+        assert!(game.extra.is_empty());
+    }
+    #[test]
+    fn game_round_trip() {
+        let mut game = Game::new();
+        game.id = 1;
+        game.name = "Toto".to_string();
+        game.added = Date::new(2022, 1, 1);
+        game.igdb_id = Some(IgdbId::new(12));
+        game.extra.insert("Rating", "good");
+        let json = serde_json::to_string(&game).unwrap();
+        assert_eq!(serde_json::from_str::<Game>(&json).unwrap(), game);
+    }
+}
+
+#[cfg(test)]
+mod test_game_id {
+    use super::*;
+    #[test]
+    fn from_name() {
+        assert_eq!(
+            GameId::from_name("AaaaaAAaaaAAAaaAAAAaAAAAA!!! for the Awesome").as_str(),
+            "aaaaaaaaaaaaaaaaaaaaaaaaa-for-the-awesome"
+        );
+        assert_eq!(GameId::from_name(" -- Été 1985 -- ").as_str(), "été-1985");
+        assert_eq!(GameId::from_name("!!!").as_str(), "game");
+    }
+    #[test]
+    fn with_discriminator() {
+        let id = GameId::from_name("Toto");
+        assert_eq!(id.with_discriminator("igdb12").as_str(), "toto--igdb12");
+        assert_eq!(id.with_discriminator("game 2").as_str(), "toto--game-2");
+        assert!(id
+            .with_discriminator("2011")
+            .as_str()
+            .parse::<GameId>()
+            .is_ok());
+        assert_ne!(id.with_discriminator("2"), GameId::from_name("Toto 2"));
+    }
+    #[test]
+    fn parse() {
+        assert!("".parse::<GameId>().is_err());
+        assert!("toto-".parse::<GameId>().is_err());
+        assert!("to--to".parse::<GameId>().is_ok());
+        assert!("to--".parse::<GameId>().is_err());
+        assert!("to--to--to".parse::<GameId>().is_err());
+        assert!("to---to".parse::<GameId>().is_err());
+        assert!("to-to".parse::<GameId>().is_ok());
     }
 }
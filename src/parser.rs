@@ -0,0 +1,489 @@
+//! # Read the games of the database as a stream
+use crate::error::{Error, ParseError, ParseErrorKind};
+use crate::models::{Field, Game, GameRef};
+use crate::utils::{check_tabs, decode_line, game_dispatch, ParsedGame};
+use std::fmt;
+use std::io::{self, BufRead};
+use std::str::Lines;
+
+/* ------------------------ PARSE OPTIONS -----------------------*/
+/// How problems found in the database are handled.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum ParseMode {
+    /// Stop at the first error and return it. Warnings are reported as
+    /// Diagnostics.
+    #[default]
+    Strict,
+    /// Keep going, skipping what cannot be parsed, and report
+    /// every problem as a Diagnostic.
+    Lenient,
+}
+
+/// # Options used when loading the database
+/// ```
+/// use pobsdlib::collections::DataBase;
+/// use pobsdlib::parser::{ParseMode, ParseOptions, Severity};
+///
+/// let input = "Cover\tcover.png\nGame\tToto\ntag\tindie\nEngine\tFNA\textra";
+/// let options = ParseOptions { mode: ParseMode::Lenient };
+/// let (db, diagnostics) = DataBase::from_reader_with_options(input.as_bytes(), &options).unwrap();
+/// assert_eq!(db.get_games_count(), 1);
+/// assert_eq!(db.get_game_by_id(1).unwrap().engine, "FNA");
+/// assert_eq!(diagnostics.len(), 3);
+/// assert_eq!(diagnostics[0].line, 1);
+/// assert_eq!(diagnostics[0].severity, Severity::Error);
+/// assert_eq!(diagnostics[1].line, 3);
+/// assert_eq!(diagnostics[1].severity, Severity::Error);
+/// assert_eq!(diagnostics[2].line, 4);
+/// assert_eq!(diagnostics[2].severity, Severity::Warning);
+/// ```
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct ParseOptions {
+    /// The parsing mode (strict by default).
+    pub mode: ParseMode,
+}
+
+/* ------------------------ DIAGNOSTIC -----------------------*/
+/// Severity of a Diagnostic.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Severity {
+    /// The line was (at least partially) used.
+    Warning,
+    /// The line was skipped.
+    Error,
+}
+
+impl Severity {
+    /// Returns the severity associated to a kind of error.
+    pub fn of(kind: ParseErrorKind) -> Self {
+        match kind {
+            ParseErrorKind::TooManyTabs => Severity::Warning,
+            ParseErrorKind::OrphanField
+            | ParseErrorKind::UnknownField
+            | ParseErrorKind::InvalidUtf8
+            | ParseErrorKind::InvalidValue => Severity::Error,
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// # Represent a problem found while loading the database
+/// Errors are only reported this way in lenient mode, warnings in both modes.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Diagnostic {
+    /// The severity of the problem.
+    pub severity: Severity,
+    /// The kind of the problem.
+    pub kind: ParseErrorKind,
+    /// The line number (starting at 1).
+    pub line: usize,
+    /// The column number (starting at 1), if known.
+    pub column: Option<usize>,
+    /// A human readable message.
+    pub message: String,
+}
+
+impl From<ParseError> for Diagnostic {
+    fn from(error: ParseError) -> Self {
+        Self {
+            severity: Severity::of(error.kind),
+            kind: error.kind,
+            line: error.line.unwrap_or_default(),
+            column: error.column,
+            message: error.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+/* ------------------------ REPORTER -----------------------*/
+/// Locates the errors and either returns them (strict mode)
+/// or stores them as diagnostics (lenient mode).
+/// The warnings are always stored as diagnostics.
+struct Reporter {
+    options: ParseOptions,
+    file: Option<String>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Reporter {
+    fn new(options: ParseOptions) -> Self {
+        Self {
+            options,
+            file: None,
+            diagnostics: Vec::new(),
+        }
+    }
+    fn report(&mut self, error: ParseError, line: usize) -> Result<(), Error> {
+        let mut error = error.with_line(line);
+        if let Some(file) = &self.file {
+            error = error.with_file(file);
+        }
+        match (self.options.mode, Severity::of(error.kind)) {
+            (ParseMode::Strict, Severity::Error) => Err(Error::Parse(error)),
+            _ => {
+                self.diagnostics.push(Diagnostic::from(error));
+                Ok(())
+            }
+        }
+    }
+}
+
+/* ------------------------ BLOCKS -----------------------*/
+/// The state shared by the readers: a game is a block of lines starting
+/// with a Game line, and is complete when the next one starts.
+struct Blocks<G> {
+    reporter: Reporter,
+    line: usize,
+    count: usize,
+    current: Option<G>,
+    done: bool,
+}
+
+impl<G> Blocks<G> {
+    fn new(options: ParseOptions) -> Self {
+        Self {
+            reporter: Reporter::new(options),
+            line: 0,
+            count: 0,
+            current: None,
+            done: false,
+        }
+    }
+    /// Reports a problem found on the current line.
+    fn report(&mut self, error: ParseError) -> Result<(), Error> {
+        self.reporter.report(error, self.line)
+    }
+    /// Parses the current line, returning the previous game if the line
+    /// starts a new one.
+    fn parse_line<'a>(&mut self, line: &'a str) -> Result<Option<G>, Error>
+    where
+        G: ParsedGame<'a>,
+    {
+        if let Err(error) = check_tabs(line) {
+            self.report(error)?;
+        }
+        let field = match Field::from(line) {
+            Ok(field) => field,
+            Err(error) => {
+                self.report(error)?;
+                return Ok(None);
+            }
+        };
+        let new_game = matches!(field, Field::NewGame(_));
+        match game_dispatch(field, &mut self.current, self.count + 1) {
+            Ok(game) => {
+                if new_game {
+                    self.count += 1;
+                }
+                Ok(game)
+            }
+            Err(error) => {
+                self.report(error)?;
+                Ok(None)
+            }
+        }
+    }
+    /// Returns what the iteration gives for the result of a line,
+    /// None if the next line has to be read.
+    fn step(&mut self, result: Result<Option<G>, Error>) -> Option<Result<G, Error>> {
+        match result {
+            Ok(game) => game.map(Ok),
+            Err(error) => self.stop(error),
+        }
+    }
+    /// Stops the iteration on the error.
+    fn stop(&mut self, error: Error) -> Option<Result<G, Error>> {
+        self.done = true;
+        Some(Err(error))
+    }
+    /// Stops the iteration at the end of the text, giving the last game.
+    fn finish(&mut self) -> Option<Result<G, Error>> {
+        self.done = true;
+        self.current.take().map(Ok)
+    }
+}
+
+/* ------------------------ GAME READER -----------------------*/
+/// # Read the games of a database one at a time
+/// The games are parsed as the lines are read, so that the memory used
+/// does not depend on the size of the database. The ids are set
+/// according to the position of the games, as in DataBase.
+/// ```
+/// use pobsdlib::parser::GameReader;
+///
+/// let input = "Game\tToto\nEngine\tFNA\nGame\tTiti\nEngine\tXNA";
+/// let mut reader = GameReader::new(input.as_bytes());
+/// let game = reader.next().unwrap().unwrap();
+/// assert_eq!(game.id, 1);
+/// assert_eq!(game.name, "Toto");
+/// assert_eq!(game.engine, "FNA");
+/// let game = reader.next().unwrap().unwrap();
+/// assert_eq!(game.id, 2);
+/// assert_eq!(game.engine, "XNA");
+/// assert!(reader.next().is_none());
+/// ```
+/// In strict mode (the default), the iteration stops after the first error.
+/// In lenient mode, the problems are stored as diagnostics
+/// (see `GameReader::diagnostics`) and no error is returned except for
+/// I/O errors.
+///
+/// The games are given as `Result<Game, Error>` rather than
+/// `Result<Game, ParseError>`: reading the lines from the source can fail
+/// on its own, and these I/O errors are returned as `Error::Io`, the
+/// parse errors being returned as `Error::Parse`.
+pub struct GameReader<R> {
+    lines: io::Split<R>,
+    blocks: Blocks<Game>,
+}
+
+impl<R: BufRead> GameReader<R> {
+    /// Create a reader using the default options.
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, ParseOptions::default())
+    }
+    /// Create a reader using the given options.
+    pub fn with_options(reader: R, options: ParseOptions) -> Self {
+        Self {
+            lines: reader.split(b'\n'),
+            blocks: Blocks::new(options),
+        }
+    }
+    /// Sets the file name used to locate the errors.
+    pub fn with_file(mut self, file: &str) -> Self {
+        self.blocks.reporter.file = Some(file.to_string());
+        self
+    }
+    /// Returns the problems found so far (only the warnings in strict mode).
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.blocks.reporter.diagnostics
+    }
+    /// Consumes the reader and returns the problems found.
+    pub fn into_diagnostics(self) -> Vec<Diagnostic> {
+        self.blocks.reporter.diagnostics
+    }
+    /// Decodes and parses a line, returning the previous game if the line
+    /// starts a new one.
+    fn parse_line(&mut self, line: Vec<u8>) -> Result<Option<Game>, Error> {
+        match decode_line(line) {
+            Ok(line) => self.blocks.parse_line(&line),
+            Err(error) => {
+                self.blocks.report(error)?;
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for GameReader<R> {
+    type Item = Result<Game, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.blocks.done {
+            return None;
+        }
+        loop {
+            let line = match self.lines.next() {
+                Some(Ok(line)) => line,
+                Some(Err(error)) => return self.blocks.stop(Error::Io(error)),
+                None => return self.blocks.finish(),
+            };
+            self.blocks.line += 1;
+            let result = self.parse_line(line);
+            if let Some(game) = self.blocks.step(result) {
+                return Some(game);
+            }
+        }
+    }
+}
+
+/* ------------------------ GAME REF READER -----------------------*/
+/// # Read the games of a database text without copying their values
+/// This is the borrowed counterpart of GameReader: the games returned
+/// are GameRef borrowing their values from the text.
+/// ```
+/// use pobsdlib::parser::GameRefReader;
+///
+/// let input = "Game\tToto\nEngine\tFNA\nGame\tTiti\nEngine\tXNA\n";
+/// let games: Vec<_> = GameRefReader::new(input).collect();
+/// assert_eq!(games.len(), 2);
+/// let game = games[1].as_ref().unwrap();
+/// assert_eq!(game.id, 2);
+/// assert_eq!(game.engine, "XNA");
+/// ```
+pub struct GameRefReader<'a> {
+    lines: Lines<'a>,
+    blocks: Blocks<GameRef<'a>>,
+}
+
+impl<'a> GameRefReader<'a> {
+    /// Create a reader using the default options.
+    pub fn new(text: &'a str) -> Self {
+        Self::with_options(text, ParseOptions::default())
+    }
+    /// Create a reader using the given options.
+    pub fn with_options(text: &'a str, options: ParseOptions) -> Self {
+        Self {
+            lines: text.lines(),
+            blocks: Blocks::new(options),
+        }
+    }
+    /// Sets the file name used to locate the errors.
+    pub fn with_file(mut self, file: &str) -> Self {
+        self.blocks.reporter.file = Some(file.to_string());
+        self
+    }
+    /// Returns the problems found so far (only the warnings in strict mode).
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.blocks.reporter.diagnostics
+    }
+    /// Consumes the reader and returns the problems found.
+    pub fn into_diagnostics(self) -> Vec<Diagnostic> {
+        self.blocks.reporter.diagnostics
+    }
+}
+
+impl<'a> Iterator for GameRefReader<'a> {
+    type Item = Result<GameRef<'a>, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.blocks.done {
+            return None;
+        }
+        loop {
+            let line = match self.lines.next() {
+                Some(line) => line,
+                None => return self.blocks.finish(),
+            };
+            self.blocks.line += 1;
+            let result = self.blocks.parse_line(line);
+            if let Some(game) = self.blocks.step(result) {
+                return Some(game);
+            }
+        }
+    }
+}
+
+/*-------------------------- TESTS --------------------------------*/
+#[cfg(test)]
+mod test_diagnostic {
+    use super::*;
+    #[test]
+    fn from_parse_error() {
+        let error = ParseError::new(ParseErrorKind::TooManyTabs, "extra", "")
+            .with_line(3)
+            .with_column(12);
+        let diagnostic = Diagnostic::from(error);
+        assert_eq!(diagnostic.severity, Severity::Warning);
+        assert_eq!(diagnostic.kind, ParseErrorKind::TooManyTabs);
+        assert_eq!(diagnostic.line, 3);
+        assert_eq!(diagnostic.column, Some(12));
+        assert_eq!(
+            diagnostic.to_string(),
+            "warning: 3:12: too many tabs, ignoring extra"
+        );
+    }
+    #[test]
+    fn reader_strict() {
+        let input = "Game\tToto\nyear\t2000\nGame\tTiti";
+        let mut reader = GameReader::new(input.as_bytes()).with_file("test.db");
+        match reader.next() {
+            Some(Err(Error::Parse(error))) => {
+                assert_eq!(error.kind, ParseErrorKind::UnknownField);
+                assert_eq!(error.line, Some(2));
+                assert_eq!(error.file, Some("test.db".to_string()));
+            }
+            _ => panic!("Should be a parse error"),
+        }
+        assert!(reader.next().is_none());
+    }
+    #[test]
+    fn reader_lenient() {
+        let input = "Engine\tFNA\nGame\tToto\nyear\t2000\nGame\tTiti";
+        let options = ParseOptions {
+            mode: ParseMode::Lenient,
+        };
+        let mut reader = GameReader::with_options(input.as_bytes(), options);
+        let mut names: Vec<String> = Vec::new();
+        for game in &mut reader {
+            names.push(game.unwrap().name);
+        }
+        assert_eq!(names, vec!["Toto".to_string(), "Titi".to_string()]);
+        let lines: Vec<usize> = reader.diagnostics().iter().map(|d| d.line).collect();
+        assert_eq!(lines, vec![1, 3]);
+    }
+    #[test]
+    fn reader_strict_warnings() {
+        let input = "Game\tToto\nEngine\tFNA\textra\nGame\tTiti";
+        let mut reader = GameReader::new(input.as_bytes());
+        assert_eq!(reader.next().unwrap().unwrap().engine, "FNA");
+        assert_eq!(reader.next().unwrap().unwrap().name, "Titi");
+        assert_eq!(reader.diagnostics().len(), 1);
+        assert_eq!(reader.diagnostics()[0].severity, Severity::Warning);
+        assert_eq!(reader.diagnostics()[0].line, 2);
+    }
+    #[test]
+    fn reader_misspelled_field() {
+        let input = "Game\tToto\nTag\tindie\nPlayers\t2";
+        let options = ParseOptions {
+            mode: ParseMode::Lenient,
+        };
+        let mut reader = GameReader::with_options(input.as_bytes(), options);
+        let game = reader.next().unwrap().unwrap();
+        assert!(game.tags.is_empty());
+        assert_eq!(game.extra.get("Tag"), None);
+        assert_eq!(game.extra.get("Players"), Some("2"));
+        assert_eq!(reader.diagnostics()[0].kind, ParseErrorKind::UnknownField);
+        assert_eq!(reader.diagnostics()[0].line, 2);
+        let mut reader = GameReader::new(input.as_bytes());
+        assert!(reader.next().unwrap().is_err());
+    }
+    #[test]
+    fn reader_empty() {
+        let mut reader = GameReader::new("".as_bytes());
+        assert!(reader.next().is_none());
+    }
+    #[test]
+    fn ref_reader_lenient() {
+        let input = "Engine\tFNA\nGame\tToto\nyear\t2000\nGame\tTiti\n";
+        let options = ParseOptions {
+            mode: ParseMode::Lenient,
+        };
+        let mut reader = GameRefReader::with_options(input, options);
+        let mut names: Vec<&str> = Vec::new();
+        for game in &mut reader {
+            names.push(game.unwrap().name);
+        }
+        assert_eq!(names, vec!["Toto", "Titi"]);
+        let lines: Vec<usize> = reader.diagnostics().iter().map(|d| d.line).collect();
+        assert_eq!(lines, vec![1, 3]);
+    }
+    #[test]
+    fn ref_reader_strict() {
+        let input = "Game\tToto\nAdded\t2000";
+        let mut reader = GameRefReader::new(input);
+        match reader.next() {
+            Some(Err(Error::Parse(error))) => {
+                assert_eq!(error.kind, ParseErrorKind::InvalidValue);
+                assert_eq!(error.line, Some(2));
+            }
+            _ => panic!("Should be a parse error"),
+        }
+        assert!(reader.next().is_none());
+    }
+    #[test]
+    fn default_mode() {
+        assert_eq!(ParseOptions::default().mode, ParseMode::Strict);
+    }
+}
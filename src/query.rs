@@ -0,0 +1,643 @@
+//! # Query the games with a boolean query language
+use crate::matching::{MatchMode, Pattern};
+use crate::models::{GameTraits, FIELD_NAMES};
+use crate::utils::{is_extra_field_name, unknown_field_hint};
+use std::cmp::Ordering;
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Maximum number of parentheses and NOT enclosing a term, so that a
+/// query cannot overflow the stack of the parser.
+pub const MAX_DEPTH: usize = 64;
+
+/* ------------------------ QUERY ERROR -----------------------*/
+/// Describes what is wrong with a query.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum QueryErrorKind {
+    /// A token is not expected at this place.
+    UnexpectedToken,
+    /// The query ends too early.
+    UnexpectedEnd,
+    /// A quoted value is not closed.
+    UnterminatedString,
+    /// The field is unknown.
+    UnknownField,
+    /// The query nests too many parentheses or NOT (see `MAX_DEPTH`).
+    TooDeep,
+}
+
+impl fmt::Display for QueryErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QueryErrorKind::UnexpectedToken => write!(f, "unexpected"),
+            QueryErrorKind::UnexpectedEnd => write!(f, "unexpected end of query"),
+            QueryErrorKind::UnterminatedString => write!(f, "unterminated string"),
+            QueryErrorKind::UnknownField => write!(f, "unknown field"),
+            QueryErrorKind::TooDeep => write!(f, "too deeply nested"),
+        }
+    }
+}
+
+/// # Represent an error encountered while parsing a query
+/// The column (starting at 1, counted in characters) locates the
+/// offending token.
+/// ```
+/// use pobsdlib::query::{Query, QueryErrorKind};
+/// let error = "tga:indie".parse::<Query>().unwrap_err();
+/// assert_eq!(error.kind, QueryErrorKind::UnknownField);
+/// assert_eq!(error.column, 1);
+/// assert_eq!(error.to_string(), "1:1: unknown field tga (did you mean Tags?)");
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct QueryError {
+    /// The kind of error.
+    pub kind: QueryErrorKind,
+    /// The column of the error (starting at 1).
+    pub column: usize,
+    /// The offending token, empty at the end of the query.
+    pub token: String,
+    /// A hint on how to fix the error.
+    pub hint: String,
+}
+
+impl QueryError {
+    /// Create a new error.
+    pub fn new(kind: QueryErrorKind, column: usize, token: &str, hint: &str) -> Self {
+        Self {
+            kind,
+            column,
+            token: token.to_string(),
+            hint: hint.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "1:{}: {}", self.column, self.kind)?;
+        if !self.token.is_empty() {
+            write!(f, " {}", self.token)?;
+        }
+        if !self.hint.is_empty() {
+            write!(f, " ({})", self.hint)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for QueryError {}
+
+/* ------------------------ AST -----------------------*/
+/// The operator of a term.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Operator {
+    /// `:`, the field contains the value.
+    Contains,
+    /// `=`, the field is the value.
+    Equal,
+    /// `!=`, the field is not the value.
+    NotEqual,
+    /// `<`
+    Less,
+    /// `<=`
+    LessOrEqual,
+    /// `>`
+    Greater,
+    /// `>=`
+    GreaterOrEqual,
+}
+
+impl Operator {
+    /// Returns the operator as written in a query.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Operator::Contains => ":",
+            Operator::Equal => "=",
+            Operator::NotEqual => "!=",
+            Operator::Less => "<",
+            Operator::LessOrEqual => "<=",
+            Operator::Greater => ">",
+            Operator::GreaterOrEqual => ">=",
+        }
+    }
+}
+
+/// # Represent a term of a query
+/// The field is the name used by the database (e.g. `Tags` for `tag`).
+#[derive(Clone, PartialEq, Debug)]
+pub struct Term {
+    pub field: String,
+    pub operator: Operator,
+    pub value: String,
+}
+
+impl Term {
+    /// Returns true if the game matches the term.
+    pub fn matches<T: GameTraits>(&self, game: &T) -> bool {
+        let mode = match self.operator {
+            Operator::Contains => MatchMode::Substring,
+            Operator::Equal | Operator::NotEqual => MatchMode::Exact,
+            operator => {
+                let values = match game.get_field(&self.field) {
+                    Ok(field) => field.values(),
+                    Err(_) => Vec::new(),
+                };
+                let value = self.value.to_lowercase();
+                return values.iter().any(|field_value| {
+                    match compare(&field_value.to_lowercase(), &value) {
+                        Some(Ordering::Less) => {
+                            operator == Operator::Less || operator == Operator::LessOrEqual
+                        }
+                        Some(Ordering::Equal) => {
+                            operator == Operator::LessOrEqual
+                                || operator == Operator::GreaterOrEqual
+                        }
+                        Some(Ordering::Greater) => {
+                            operator == Operator::Greater || operator == Operator::GreaterOrEqual
+                        }
+                        None => false,
+                    }
+                });
+            }
+        };
+        let matches = match Pattern::new(&self.value, mode) {
+            Ok(pattern) => game.field_matches(&self.field, &pattern),
+            Err(_) => false,
+        };
+        matches != (self.operator == Operator::NotEqual)
+    }
+}
+
+/// Compares a value of a field with the value of a term, as numbers if
+/// both are numbers. Empty values cannot be compared.
+fn compare(left: &str, right: &str) -> Option<Ordering> {
+    if left.is_empty() {
+        return None;
+    }
+    match (left.parse::<f64>(), right.parse::<f64>()) {
+        (Ok(left), Ok(right)) => left.partial_cmp(&right),
+        _ => Some(left.cmp(right)),
+    }
+}
+
+/// # Represent a query
+/// A query is made of terms combined with `AND`, `OR` and `NOT`, and
+/// grouped with parentheses. `NOT` binds tighter than `AND`, which binds
+/// tighter than `OR`. `AND` can be omitted.
+///
+/// A term is a field name, an operator and a value:
+/// - `field:value` is true if the field contains the value,
+/// - `field=value` is true if the field is the value,
+/// - `field!=value` is true if the field is not the value,
+/// - `field<value`, `field<=value`, `field>value` and `field>=value`
+///   compare the field with the value, as numbers if both are numbers
+///   and as text otherwise.
+///
+/// The comparisons are not case sensitive. A field with several values
+/// matches if one of them does (none of them for `!=`). The values
+/// containing spaces or parentheses must be quoted: `dev:"Nitrome Ltd"`.
+/// `tag`, `genre` and `name` can be used for `Tags`, `Genre` and `Game`.
+/// ```
+/// use pobsdlib::collections::DataBase;
+/// use pobsdlib::query::Query;
+/// let db = DataBase::open("tests/data/test-games.db").unwrap();
+/// let query: Query = "tag:indie AND NOT engine:unity AND year>=2010 OR runtime:\"fnaify\""
+///     .parse()
+///     .unwrap();
+/// let games = db.get_games_by_query(&query);
+/// assert_eq!(games.count, 2);
+/// let error = "tag:indie AND (genre:rpg".parse::<Query>().unwrap_err();
+/// assert_eq!(error.to_string(), "1:25: unexpected end of query (expected ')')");
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub enum Query {
+    Term(Term),
+    Not(Box<Query>),
+    And(Vec<Query>),
+    Or(Vec<Query>),
+}
+
+impl Query {
+    /// Parses a query. Returns a QueryError if it cannot.
+    pub fn parse(input: &str) -> Result<Self, QueryError> {
+        let mut parser = Parser {
+            chars: input.chars().collect(),
+            position: 0,
+            depth: 0,
+        };
+        let query = parser.parse_or()?;
+        parser.skip_whitespaces();
+        match parser.peek() {
+            None => Ok(query),
+            Some(_) => Err(parser.unexpected("expected AND, OR or the end of the query")),
+        }
+    }
+    /// Returns true if the game matches the query.
+    pub fn matches<T: GameTraits>(&self, game: &T) -> bool {
+        match self {
+            Query::Term(term) => term.matches(game),
+            Query::Not(query) => !query.matches(game),
+            Query::And(queries) => queries.iter().all(|query| query.matches(game)),
+            Query::Or(queries) => queries.iter().any(|query| query.matches(game)),
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Query::parse(input)
+    }
+}
+
+impl fmt::Display for Query {
+    /// Writes the query with explicit operators and parentheses.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let write_all = |f: &mut fmt::Formatter, queries: &[Query], separator: &str| {
+            write!(f, "(")?;
+            for (index, query) in queries.iter().enumerate() {
+                if index > 0 {
+                    write!(f, " {} ", separator)?;
+                }
+                write!(f, "{}", query)?;
+            }
+            write!(f, ")")
+        };
+        match self {
+            Query::Term(term) => {
+                write!(f, "{}{}", term.field, term.operator.as_str())?;
+                if term.value.is_empty()
+                    || term
+                        .value
+                        .chars()
+                        .any(|c| c.is_whitespace() || c == '(' || c == ')' || c == '"')
+                {
+                    write!(
+                        f,
+                        "\"{}\"",
+                        term.value.replace('\\', "\\\\").replace('"', "\\\"")
+                    )
+                } else {
+                    write!(f, "{}", term.value)
+                }
+            }
+            Query::Not(query) => write!(f, "NOT {}", query),
+            Query::And(queries) => write_all(f, queries, "AND"),
+            Query::Or(queries) => write_all(f, queries, "OR"),
+        }
+    }
+}
+
+/* ------------------------ PARSER -----------------------*/
+/// Returns the name used by the database for a field of a query.
+fn field_name(name: &str) -> Option<String> {
+    let lowercase = name.to_lowercase();
+    let alias = match lowercase.as_str() {
+        "tag" => Some("Tags"),
+        "genres" => Some("Genre"),
+        "name" => Some("Game"),
+        _ => None,
+    };
+    match alias {
+        Some(field) => Some(field.to_string()),
+        None => match FIELD_NAMES
+            .iter()
+            .find(|field| field.to_lowercase() == lowercase)
+        {
+            Some(field) => Some(field.to_string()),
+            None if is_extra_field_name(name) => Some(name.to_string()),
+            None => None,
+        },
+    }
+}
+
+/// A recursive descent parser, the position being the index of the
+/// next character.
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    /// Number of parentheses and NOT enclosing the current position.
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).cloned()
+    }
+    fn skip_whitespaces(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+    /// Returns true if the keyword is next (followed by a separator).
+    fn is_keyword(&self, keyword: &str) -> bool {
+        let end = self.position + keyword.len();
+        end <= self.chars.len()
+            && self.chars[self.position..end]
+                .iter()
+                .cloned()
+                .eq(keyword.chars())
+            && self
+                .chars
+                .get(end)
+                .map_or(true, |c| c.is_whitespace() || *c == '(' || *c == '"')
+    }
+    /// Consumes the keyword if it is next.
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        self.skip_whitespaces();
+        if self.is_keyword(keyword) {
+            self.position += keyword.len();
+            true
+        } else {
+            false
+        }
+    }
+    /// Returns an error for the next token.
+    fn unexpected(&self, hint: &str) -> QueryError {
+        let start = self.position;
+        match self.peek() {
+            None => QueryError::new(QueryErrorKind::UnexpectedEnd, start + 1, "", hint),
+            Some(c) if is_word_char(c) => {
+                let token: String = self.chars[start..]
+                    .iter()
+                    .take_while(|c| is_word_char(**c))
+                    .collect();
+                QueryError::new(QueryErrorKind::UnexpectedToken, start + 1, &token, hint)
+            }
+            Some(c) => QueryError::new(
+                QueryErrorKind::UnexpectedToken,
+                start + 1,
+                &c.to_string(),
+                hint,
+            ),
+        }
+    }
+    /// Goes one level deeper, returning an error past `MAX_DEPTH`.
+    fn enter(&mut self, column: usize) -> Result<(), QueryError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(QueryError::new(
+                QueryErrorKind::TooDeep,
+                column,
+                "",
+                &format!("at most {} levels are allowed", MAX_DEPTH),
+            ));
+        }
+        Ok(())
+    }
+    fn parse_or(&mut self) -> Result<Query, QueryError> {
+        let mut queries = vec![self.parse_and()?];
+        while self.eat_keyword("OR") {
+            queries.push(self.parse_and()?);
+        }
+        Ok(if queries.len() == 1 {
+            queries.remove(0)
+        } else {
+            Query::Or(queries)
+        })
+    }
+    fn parse_and(&mut self) -> Result<Query, QueryError> {
+        let mut queries = vec![self.parse_not()?];
+        loop {
+            self.skip_whitespaces();
+            if self.peek().is_none() || self.peek() == Some(')') || self.is_keyword("OR") {
+                break;
+            }
+            self.eat_keyword("AND");
+            queries.push(self.parse_not()?);
+        }
+        Ok(if queries.len() == 1 {
+            queries.remove(0)
+        } else {
+            Query::And(queries)
+        })
+    }
+    fn parse_not(&mut self) -> Result<Query, QueryError> {
+        if self.eat_keyword("NOT") {
+            self.enter(self.position - 2)?;
+            let query = self.parse_not()?;
+            self.depth -= 1;
+            Ok(Query::Not(Box::new(query)))
+        } else {
+            self.parse_primary()
+        }
+    }
+    fn parse_primary(&mut self) -> Result<Query, QueryError> {
+        self.skip_whitespaces();
+        if self.peek() == Some('(') {
+            self.position += 1;
+            self.enter(self.position)?;
+            let query = self.parse_or()?;
+            self.skip_whitespaces();
+            if self.peek() != Some(')') {
+                return Err(self.unexpected("expected ')'"));
+            }
+            self.position += 1;
+            self.depth -= 1;
+            Ok(query)
+        } else {
+            Ok(Query::Term(self.parse_term()?))
+        }
+    }
+    fn parse_term(&mut self) -> Result<Term, QueryError> {
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            self.position += 1;
+        }
+        if start == self.position {
+            return Err(self.unexpected("expected a field name"));
+        }
+        let name: String = self.chars[start..self.position].iter().collect();
+        let field = match field_name(&name) {
+            Some(field) => field,
+            None => {
+                return Err(QueryError::new(
+                    QueryErrorKind::UnknownField,
+                    start + 1,
+                    &name,
+                    &unknown_field_hint(&name),
+                ))
+            }
+        };
+        let operator = self.parse_operator()?;
+        let value = self.parse_value()?;
+        Ok(Term {
+            field,
+            operator,
+            value,
+        })
+    }
+    fn parse_operator(&mut self) -> Result<Operator, QueryError> {
+        let next = self.chars.get(self.position + 1) == Some(&'=');
+        let (operator, length) = match self.peek() {
+            Some(':') => (Operator::Contains, 1),
+            Some('=') => (Operator::Equal, 1),
+            Some('!') if next => (Operator::NotEqual, 2),
+            Some('<') if next => (Operator::LessOrEqual, 2),
+            Some('<') => (Operator::Less, 1),
+            Some('>') if next => (Operator::GreaterOrEqual, 2),
+            Some('>') => (Operator::Greater, 1),
+            _ => return Err(self.unexpected("expected one of : = != < <= > >=")),
+        };
+        self.position += length;
+        Ok(operator)
+    }
+    fn parse_value(&mut self) -> Result<String, QueryError> {
+        let start = self.position;
+        if self.peek() == Some('"') {
+            self.position += 1;
+            let mut value = String::new();
+            loop {
+                match self.peek() {
+                    Some('"') => {
+                        self.position += 1;
+                        return Ok(value);
+                    }
+                    Some('\\') if self.chars.get(self.position + 1).is_some() => {
+                        value.push(self.chars[self.position + 1]);
+                        self.position += 2;
+                    }
+                    Some(c) => {
+                        value.push(c);
+                        self.position += 1;
+                    }
+                    None => {
+                        let token: String = self.chars[start..].iter().collect();
+                        return Err(QueryError::new(
+                            QueryErrorKind::UnterminatedString,
+                            start + 1,
+                            &token,
+                            "expected a closing '\"'",
+                        ));
+                    }
+                }
+            }
+        }
+        while self.peek().is_some_and(is_word_char) {
+            self.position += 1;
+        }
+        if start == self.position {
+            return Err(self.unexpected("expected a value"));
+        }
+        Ok(self.chars[start..self.position].iter().collect())
+    }
+}
+
+/// Returns true if the character can be part of an unquoted value.
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && c != '(' && c != ')' && c != '"'
+}
+
+/*-------------------------- TESTS --------------------------------*/
+#[cfg(test)]
+mod test_query_parser {
+    use super::*;
+    fn term(field: &str, operator: Operator, value: &str) -> Query {
+        Query::Term(Term {
+            field: field.to_string(),
+            operator,
+            value: value.to_string(),
+        })
+    }
+    #[test]
+    fn precedence() {
+        let query = Query::parse("tag:indie AND genre:rpg OR NOT year>=2010").unwrap();
+        assert_eq!(
+            query,
+            Query::Or(vec![
+                Query::And(vec![
+                    term("Tags", Operator::Contains, "indie"),
+                    term("Genre", Operator::Contains, "rpg"),
+                ]),
+                Query::Not(Box::new(term("Year", Operator::GreaterOrEqual, "2010"))),
+            ])
+        );
+    }
+    #[test]
+    fn implicit_and_and_parentheses() {
+        let query = Query::parse("(tag:indie OR tag:puzzle) dev!=\"Nitrome Ltd\"").unwrap();
+        assert_eq!(
+            query.to_string(),
+            "((Tags:indie OR Tags:puzzle) AND Dev!=\"Nitrome Ltd\")"
+        );
+        assert_eq!(Query::parse(&query.to_string()).unwrap(), query);
+    }
+    #[test]
+    fn values() {
+        let query = Query::parse("store:https://www.gog.com Rating=\"a \\\"b\\\"\"").unwrap();
+        assert_eq!(
+            query,
+            Query::And(vec![
+                term("Store", Operator::Contains, "https://www.gog.com"),
+                term("Rating", Operator::Equal, "a \"b\""),
+            ])
+        );
+        // a field starting like a keyword is not a keyword
+        assert_eq!(
+            Query::parse("ANDROID:x").unwrap(),
+            term("ANDROID", Operator::Contains, "x")
+        );
+    }
+    #[test]
+    fn errors() {
+        let error = Query::parse("tag:indie AND").unwrap_err();
+        assert_eq!(error.kind, QueryErrorKind::UnexpectedEnd);
+        assert_eq!(error.column, 14);
+        let error = Query::parse("tag indie").unwrap_err();
+        assert_eq!(error.kind, QueryErrorKind::UnexpectedToken);
+        assert_eq!(error.column, 4);
+        let error = Query::parse("tag:").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "1:5: unexpected end of query (expected a value)"
+        );
+        let error = Query::parse("tag:indie)").unwrap_err();
+        assert_eq!(error.column, 10);
+        let error = Query::parse("dev:\"Nitrome").unwrap_err();
+        assert_eq!(error.kind, QueryErrorKind::UnterminatedString);
+        assert_eq!(error.column, 5);
+    }
+    #[test]
+    fn depth() {
+        let nested = |depth| format!("{}tag:indie{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(Query::parse(&nested(MAX_DEPTH)).is_ok());
+        let error = Query::parse(&nested(MAX_DEPTH + 1)).unwrap_err();
+        assert_eq!(error.kind, QueryErrorKind::TooDeep);
+        assert_eq!(error.column, MAX_DEPTH + 1);
+        let error = Query::parse(&"NOT ".repeat(100_000)).unwrap_err();
+        assert_eq!(error.kind, QueryErrorKind::TooDeep);
+        assert_eq!(error.column, 4 * MAX_DEPTH + 1);
+        assert!(Query::parse(&"(".repeat(100_000)).is_err());
+    }
+}
+
+#[cfg(test)]
+mod test_query_evaluation {
+    use super::*;
+    use utils::load_test_games;
+    fn matches(query: &str) -> bool {
+        let games = load_test_games("Game\tToto\nTags\tindie, pixel art\nYear\t2011\nEngine\tFNA");
+        Query::parse(query).unwrap().matches(&games.items[0])
+    }
+    #[test]
+    fn operators() {
+        assert!(matches("name:to"));
+        assert!(matches("tag=INDIE"));
+        assert!(!matches("tag=pixel"));
+        assert!(matches("tag!=pixel"));
+        assert!(!matches("tag!=indie"));
+        assert!(matches("year>=2011 year<2012 year>2010.5"));
+        assert!(!matches("year>2011"));
+        assert!(!matches("dev<z"));
+        assert!(matches("engine<=fna"));
+    }
+    #[test]
+    fn combinations() {
+        assert!(matches("tag:indie AND NOT engine:unity"));
+        assert!(!matches("tag:indie AND NOT engine:fna"));
+        assert!(matches("tag:rpg OR engine:fna"));
+        assert!(matches("NOT NOT tag:indie"));
+        assert!(!matches("Rating:good"));
+    }
+}
@@ -0,0 +1,359 @@
+//! # Search the games with free text
+use crate::collections::ItemCollection;
+use crate::models::GameTraits;
+use crate::utils::{edit_distance, fold_name};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// The fields of the games being indexed.
+pub const SEARCH_FIELDS: [&str; 8] = [
+    "Game", "Tags", "Genre", "Engine", "Runtime", "Dev", "Pub", "Hints",
+];
+
+/// BM25 parameter for the saturation of the term frequency.
+const K1: f64 = 1.2;
+/// BM25 parameter for the normalization by the length of the field.
+const B: f64 = 0.75;
+
+/* ------------------------ WEIGHTS -----------------------*/
+/// # The weight of each field in the score of a result
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct FieldWeights {
+    pub name: f64,
+    pub tags: f64,
+    pub genres: f64,
+    pub engine: f64,
+    pub runtime: f64,
+    pub dev: f64,
+    pub publi: f64,
+    pub hints: f64,
+}
+
+impl Default for FieldWeights {
+    fn default() -> Self {
+        Self {
+            name: 3.0,
+            tags: 2.0,
+            genres: 2.0,
+            engine: 1.5,
+            runtime: 1.5,
+            dev: 1.0,
+            publi: 1.0,
+            hints: 0.5,
+        }
+    }
+}
+
+impl FieldWeights {
+    /// Returns the weights in the order of SEARCH_FIELDS.
+    fn as_array(&self) -> [f64; 8] {
+        [
+            self.name,
+            self.tags,
+            self.genres,
+            self.engine,
+            self.runtime,
+            self.dev,
+            self.publi,
+            self.hints,
+        ]
+    }
+}
+
+/* ------------------------ RESULT -----------------------*/
+/// # Represent a game found by a search
+#[derive(Serialize, Debug)]
+pub struct SearchResult<'a, T: 'a> {
+    /// The game found.
+    pub item: &'a T,
+    /// The score of the game, the higher the better.
+    pub score: f64,
+    /// The fields containing at least one of the words searched, in the
+    /// order of SEARCH_FIELDS.
+    pub fields: Vec<&'static str>,
+}
+
+/* ------------------------ INDEX -----------------------*/
+/// An occurrence of a word in a field of a game.
+struct Posting {
+    position: usize,
+    field: usize,
+    frequency: usize,
+}
+
+/// # Represent an inverted index of the games
+/// The index covers the name, tags, genres, engine, runtime, developer,
+/// publisher and hints of the games. The results are ranked with BM25,
+/// each field having its own weight (see `FieldWeights`).
+/// The index borrows the collection it was built from.
+/// ```
+/// use pobsdlib::collections::DataBase;
+/// use pobsdlib::search::SearchIndex;
+/// let db = DataBase::open("tests/data/test-games.db").unwrap();
+/// let index = SearchIndex::new(&db.games);
+/// let results = index.search("puzzle platformer");
+/// assert_eq!(results[0].item.name, "The Adventures of Shuggy");
+/// assert_eq!(results[0].fields, vec!["Genre"]);
+/// assert!(results[0].score > results[1].score);
+/// ```
+pub struct SearchIndex<'a, T: 'a> {
+    items: &'a [T],
+    weights: [f64; 8],
+    postings: HashMap<String, Vec<Posting>>,
+    /// The number of words of each field, by position of the game.
+    lengths: Vec<[usize; 8]>,
+    /// The average number of words of each field.
+    averages: [f64; 8],
+}
+
+/// Splits the text into lower case words.
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+impl<'a, T: GameTraits> SearchIndex<'a, T> {
+    /// Builds the index of the games, using the default weights.
+    pub fn new(games: &'a ItemCollection<T>) -> Self {
+        Self::with_weights(games, FieldWeights::default())
+    }
+    /// Builds the index of the games, using the given weights.
+    pub fn with_weights(games: &'a ItemCollection<T>, weights: FieldWeights) -> Self {
+        let mut postings: HashMap<String, Vec<Posting>> = HashMap::new();
+        let mut lengths: Vec<[usize; 8]> = Vec::new();
+        let mut totals = [0usize; 8];
+        for (position, game) in games.items.iter().enumerate() {
+            let mut game_lengths = [0usize; 8];
+            for (field, name) in SEARCH_FIELDS.iter().enumerate() {
+                let values = match game.get_field(name) {
+                    Ok(field) => field.values(),
+                    Err(_) => Vec::new(),
+                };
+                let mut frequencies: HashMap<String, usize> = HashMap::new();
+                for value in values {
+                    for word in tokenize(value) {
+                        game_lengths[field] += 1;
+                        *frequencies.entry(word).or_insert(0) += 1;
+                    }
+                }
+                for (word, frequency) in frequencies {
+                    postings.entry(word).or_default().push(Posting {
+                        position,
+                        field,
+                        frequency,
+                    });
+                }
+                totals[field] += game_lengths[field];
+            }
+            lengths.push(game_lengths);
+        }
+        let mut averages = [0f64; 8];
+        if !lengths.is_empty() {
+            for (average, total) in averages.iter_mut().zip(totals.iter()) {
+                *average = *total as f64 / lengths.len() as f64;
+            }
+        }
+        Self {
+            items: &games.items,
+            weights: weights.as_array(),
+            postings,
+            lengths,
+            averages,
+        }
+    }
+    /// Returns the games containing at least one of the words of the
+    /// text, the best ones first. Games with the same score are given
+    /// in the collection order.
+    pub fn search(&self, text: &str) -> Vec<SearchResult<'a, T>> {
+        let mut words = tokenize(text);
+        words.sort();
+        words.dedup();
+        let count = self.items.len() as f64;
+        let mut scores: HashMap<usize, (f64, [bool; 8])> = HashMap::new();
+        for word in &words {
+            let postings = match self.postings.get(word) {
+                Some(postings) => postings,
+                None => continue,
+            };
+            let mut positions: Vec<usize> = postings.iter().map(|p| p.position).collect();
+            positions.dedup();
+            let found = positions.len() as f64;
+            let idf = (1.0 + (count - found + 0.5) / (found + 0.5)).ln();
+            for posting in postings {
+                let length = self.lengths[posting.position][posting.field] as f64;
+                // the average is not null as the field has words
+                let average = self.averages[posting.field];
+                let frequency = posting.frequency as f64;
+                let score = self.weights[posting.field] * idf * frequency * (K1 + 1.0)
+                    / (frequency + K1 * (1.0 - B + B * length / average));
+                let entry = scores.entry(posting.position).or_insert((0.0, [false; 8]));
+                entry.0 += score;
+                entry.1[posting.field] = true;
+            }
+        }
+        let mut results: Vec<(usize, f64, [bool; 8])> = scores
+            .into_iter()
+            .map(|(position, (score, fields))| (position, score, fields))
+            .collect();
+        results.sort_by(|left, right| {
+            right
+                .1
+                .partial_cmp(&left.1)
+                .unwrap_or(Ordering::Equal)
+                .then(left.0.cmp(&right.0))
+        });
+        results
+            .into_iter()
+            .map(|(position, score, fields)| SearchResult {
+                item: &self.items[position],
+                score,
+                fields: SEARCH_FIELDS
+                    .iter()
+                    .zip(fields.iter())
+                    .filter(|(_, found)| **found)
+                    .map(|(name, _)| *name)
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+/* ------------------------ FUZZY -----------------------*/
+/// The similarity below which a name is not considered as a candidate.
+pub const MIN_SIMILARITY: f64 = 0.5;
+/// The factor applied to the similarity computed word by word, so that
+/// a name matching as a whole comes first.
+const WORD_FACTOR: f64 = 0.9;
+
+/// # Represent a name found by a fuzzy lookup
+#[derive(Serialize, Debug)]
+pub struct FuzzyMatch<'a, T: 'a> {
+    /// The item found.
+    pub item: &'a T,
+    /// The similarity of the name, between 0 and 1 (equal names).
+    pub similarity: f64,
+}
+
+/// Returns the similarity of two folded strings, based on the edit distance.
+fn ratio(left: &str, right: &str) -> f64 {
+    let length = left.chars().count().max(right.chars().count());
+    if length == 0 {
+        return 0.0;
+    }
+    1.0 - edit_distance(left, right) as f64 / length as f64
+}
+
+/// # Compare names to a name typed by the user
+/// The names are compared ignoring case, punctuation and diacritics,
+/// as a whole and word by word, so that a part of a long name is enough.
+/// ```
+/// use pobsdlib::search::FuzzyMatcher;
+/// let matcher = FuzzyMatcher::new("pokemon");
+/// assert_eq!(matcher.similarity("Pokémon!"), 1.0);
+/// assert_eq!(matcher.similarity("Pokemon Snap"), 0.9);
+/// assert!(matcher.similarity("Pokemom") > matcher.similarity("Shuggy"));
+/// assert_eq!(matcher.similarity("!!!"), 0.0);
+/// ```
+pub struct FuzzyMatcher {
+    name: String,
+    words: Vec<String>,
+}
+
+impl FuzzyMatcher {
+    /// Create a matcher for the name.
+    pub fn new(name: &str) -> Self {
+        let name = fold_name(name);
+        let words = name
+            .split_whitespace()
+            .map(|word| word.to_string())
+            .collect();
+        Self { name, words }
+    }
+    /// Returns the similarity of the text to the name, between 0 and 1.
+    pub fn similarity(&self, text: &str) -> f64 {
+        let text = fold_name(text);
+        if self.words.is_empty() || text.is_empty() {
+            return 0.0;
+        }
+        let whole = ratio(&self.name, &text);
+        let text_words: Vec<&str> = text.split(' ').collect();
+        let mut words = 0.0;
+        for word in &self.words {
+            words += text_words
+                .iter()
+                .map(|text_word| ratio(word, text_word))
+                .fold(0.0, f64::max);
+        }
+        whole.max(WORD_FACTOR * words / self.words.len() as f64)
+    }
+}
+
+/*-------------------------- TESTS --------------------------------*/
+#[cfg(test)]
+mod test_search {
+    use super::*;
+    use models::Game;
+    use utils::load_test_games;
+    fn games() -> ItemCollection<Game> {
+        load_test_games(
+            "Game\tPuzzle Quest\n\
+             Game\tToto\nHints\tA puzzle in the hints\n\
+             Game\tTiti\nGenre\tPuzzle, Platformer\nEngine\tFNA",
+        )
+    }
+    #[test]
+    fn tokenize_words() {
+        assert_eq!(
+            tokenize("Puzzle-Platformer, FNA!"),
+            vec!["puzzle", "platformer", "fna"]
+        );
+    }
+    #[test]
+    fn weights() {
+        let games = games();
+        let index = SearchIndex::new(&games);
+        let results = index.search("puzzle");
+        let names: Vec<&str> = results.iter().map(|r| r.item.name.as_str()).collect();
+        assert_eq!(names, vec!["Puzzle Quest", "Titi", "Toto"]);
+        assert_eq!(results[2].fields, vec!["Hints"]);
+        let weights = FieldWeights {
+            hints: 10.0,
+            ..Default::default()
+        };
+        let index = SearchIndex::with_weights(&games, weights);
+        assert_eq!(index.search("puzzle")[0].item.name, "Toto");
+    }
+    #[test]
+    fn several_words() {
+        let games = games();
+        let index = SearchIndex::new(&games);
+        let results = index.search("puzzle platformer fna");
+        assert_eq!(results[0].item.name, "Titi");
+        assert_eq!(results[0].fields, vec!["Genre", "Engine"]);
+        assert_eq!(results.len(), 3);
+        assert!(index.search("unknown").is_empty());
+        assert!(index.search("").is_empty());
+    }
+}
+
+#[cfg(test)]
+mod test_fuzzy {
+    use super::*;
+    #[test]
+    fn ratio_bounds() {
+        assert_eq!(ratio("", ""), 0.0);
+        assert_eq!(ratio("abc", "abc"), 1.0);
+        assert_eq!(ratio("abcd", "abce"), 0.75);
+        assert_eq!(ratio("abc", "xyz"), 0.0);
+    }
+    #[test]
+    fn whole_name_first() {
+        let matcher = FuzzyMatcher::new("for the awesome");
+        let name = "AaaaaAAaaaAAAaaAAAAaAAAAA!!! for the Awesome";
+        assert!(matcher.similarity(name) >= MIN_SIMILARITY);
+        assert!(matcher.similarity("For The Awesome") > matcher.similarity(name));
+        assert!(matcher.similarity("Shuggy") < MIN_SIMILARITY);
+    }
+}
@@ -0,0 +1,195 @@
+//! # Sort and paginate the games
+use crate::error::{ParseError, ParseErrorKind};
+use crate::models::{GameTraits, ItemTraits};
+use crate::utils::fold_name;
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+/// The articles ignored when sorting by name.
+const ARTICLES: [&str; 3] = ["the ", "a ", "an "];
+
+/* ------------------------ KEYS -----------------------*/
+/// # The values the games can be sorted by
+/// The games without value are always given last, and the games with
+/// the same value are ordered by name, then by id, so that the order
+/// does not depend on the database order.
+/// ```
+/// use pobsdlib::collections::DataBase;
+/// use pobsdlib::sorting::{SortKey, SortOrder};
+/// let db = DataBase::open("tests/data/test-games.db").unwrap();
+/// let mut games = db.get_games_by_tag("indie");
+/// games.sort_items(SortKey::Year, SortOrder::Descending);
+/// let page = games.into_page(0, 1);
+/// assert_eq!(page.total, 2);
+/// assert_eq!(page.items[0].name, "Aeternum");
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum SortKey {
+    /// The name, ignoring a leading "The", "A" or "An".
+    #[default]
+    Name,
+    Year,
+    Engine,
+    Dev,
+    Added,
+    Updated,
+}
+
+impl SortKey {
+    /// Returns the name of the field sorted by.
+    pub fn field_name(&self) -> &'static str {
+        match self {
+            SortKey::Name => "Game",
+            SortKey::Year => "Year",
+            SortKey::Engine => "Engine",
+            SortKey::Dev => "Dev",
+            SortKey::Added => "Added",
+            SortKey::Updated => "Updated",
+        }
+    }
+    /// Returns the value of the game compared when sorting.
+    pub fn sort_value<T: GameTraits + ItemTraits>(&self, game: &T) -> SortValue {
+        let field = game
+            .get_field(self.field_name())
+            .map(|field| field.values().join(" "))
+            .unwrap_or_default();
+        let name = name_key(game.get_name());
+        let value = match self {
+            SortKey::Name => name.clone(),
+            SortKey::Engine | SortKey::Dev => fold_name(&field),
+            SortKey::Year | SortKey::Added | SortKey::Updated => field.trim().to_string(),
+        };
+        SortValue {
+            value,
+            name,
+            id: game.get_id(),
+        }
+    }
+}
+
+impl FromStr for SortKey {
+    type Err = ParseError;
+    /// Parses a sort key from its field name (not case sensitive).
+    /// "name" is accepted as well as "game".
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let key = match text.to_lowercase().as_str() {
+            "game" | "name" => SortKey::Name,
+            "year" => SortKey::Year,
+            "engine" => SortKey::Engine,
+            "dev" => SortKey::Dev,
+            "added" => SortKey::Added,
+            "updated" => SortKey::Updated,
+            _ => {
+                return Err(ParseError::new(
+                    ParseErrorKind::InvalidValue,
+                    text,
+                    "expected one of name, year, engine, dev, added or updated",
+                ))
+            }
+        };
+        Ok(key)
+    }
+}
+
+/// # The order of the games
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum SortOrder {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+/// Returns the folded name without its leading article.
+fn name_key(name: &str) -> String {
+    let name = fold_name(name);
+    for article in &ARTICLES {
+        if let Some(rest) = name.strip_prefix(article) {
+            return rest.to_string();
+        }
+    }
+    name
+}
+
+/// # The value of a game compared when sorting, with its tie-breakers
+/// See `SortKey::sort_value`.
+pub struct SortValue {
+    value: String,
+    name: String,
+    id: usize,
+}
+
+impl SortValue {
+    /// Compares two values in the given order, the empty values being
+    /// last and the tie-breakers always ascending.
+    pub fn compare(&self, other: &SortValue, order: SortOrder) -> Ordering {
+        let value = match (self.value.is_empty(), other.value.is_empty()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => match order {
+                SortOrder::Ascending => self.value.cmp(&other.value),
+                SortOrder::Descending => other.value.cmp(&self.value),
+            },
+        };
+        value
+            .then_with(|| self.name.cmp(&other.name))
+            .then(self.id.cmp(&other.id))
+    }
+}
+
+/* ------------------------ PAGE -----------------------*/
+/// # Represent a page of items
+/// See `ItemCollection::into_page`.
+#[derive(Serialize, Debug)]
+pub struct Page<T> {
+    /// The number of items in all the pages.
+    pub total: usize,
+    /// The position of the first item of the page among all the items.
+    pub offset: usize,
+    /// The number of items in the page.
+    pub count: usize,
+    /// The items of the page.
+    pub items: Vec<T>,
+}
+
+/*-------------------------- TESTS --------------------------------*/
+#[cfg(test)]
+mod test_sorting {
+    use super::*;
+    #[test]
+    fn articles() {
+        assert_eq!(name_key("The Adventures of Shuggy"), "adventures of shuggy");
+        assert_eq!(name_key("A Story About My Uncle"), "story about my uncle");
+        assert_eq!(name_key("Theme Hospital"), "theme hospital");
+        assert_eq!(name_key("The"), "the");
+    }
+    #[test]
+    fn empty_values_last() {
+        let value = |value: &str, id| SortValue {
+            value: value.to_string(),
+            name: String::new(),
+            id,
+        };
+        for order in &[SortOrder::Ascending, SortOrder::Descending] {
+            assert_eq!(
+                value("", 1).compare(&value("2000", 2), *order),
+                Ordering::Greater
+            );
+        }
+        assert_eq!(
+            value("2000", 1).compare(&value("2010", 2), SortOrder::Descending),
+            Ordering::Greater
+        );
+        assert_eq!(
+            value("2000", 2).compare(&value("2000", 1), SortOrder::Descending),
+            Ordering::Greater
+        );
+    }
+    #[test]
+    fn parse_key() {
+        assert_eq!("Name".parse::<SortKey>().unwrap(), SortKey::Name);
+        assert_eq!("updated".parse::<SortKey>().unwrap(), SortKey::Updated);
+        let error = "genre".parse::<SortKey>().unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::InvalidValue);
+    }
+}
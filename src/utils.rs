@@ -1,8 +1,13 @@
 use crate::collections::ItemCollection;
-use crate::models::{Field, Game, GameTraitsMut, Item, ItemTraitsMut};
+use crate::error::{Error, ParseError, ParseErrorKind};
+use crate::models::{
+    Field, Game, GameId, GameRef, GameTraitsMut, Item, ItemTraits, ItemTraitsMut, FIELD_NAMES,
+};
+use crate::parser::{Diagnostic, GameReader, ParseOptions};
+use std::collections::HashMap;
 use std::fs::File;
+use std::hash::Hash;
 use std::io::{self, BufRead};
-use std::path::Path;
 
 pub fn split_line(line: &str) -> (&str, &str) {
     let split_line: Vec<&str> = line.split('\t').collect();
@@ -21,91 +26,700 @@ pub fn split_line(line: &str) -> (&str, &str) {
         _ => {
             left = split_line[0];
             right = split_line[1];
-            eprintln!(
-                "WARNING: ignoring {}. Check tabs number in your database",
-                split_line[2]
-            );
         }
     };
     (left, right)
 }
 
+/// Returns a ParseError (without line number) if the line has more than one tab.
+pub fn check_tabs(line: &str) -> Result<(), ParseError> {
+    let mut tabs = line.match_indices('\t');
+    match (tabs.next(), tabs.next()) {
+        (Some(_), Some((index, _))) => Err(ParseError::new(
+            ParseErrorKind::TooManyTabs,
+            &line[index + 1..],
+            "fields must be separated from their value by a single tab",
+        )
+        .with_column(line[..index].chars().count() + 1)),
+        _ => Ok(()),
+    }
+}
+#[cfg(test)]
+mod tests_check_tabs {
+    use super::*;
+    #[test]
+    fn test_one_tab() {
+        assert!(check_tabs("one\ttab").is_ok());
+        assert!(check_tabs("notab").is_ok());
+    }
+    #[test]
+    fn test_two_tab() {
+        let error = check_tabs("one\ttab\tanother").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::TooManyTabs);
+        assert_eq!(error.key, "another");
+        assert_eq!(error.column, Some(8));
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     #[test]
     fn test_empty() {
         let test_str = "";
-        assert_eq!(("", ""), split_line(&test_str));
+        assert_eq!(("", ""), split_line(test_str));
     }
     #[test]
     fn test_no_tab() {
         let test_str = "notab";
-        assert_eq!(("notab", ""), split_line(&test_str));
+        assert_eq!(("notab", ""), split_line(test_str));
     }
     #[test]
     fn test_no_tab_space() {
         let test_str = "no tab";
-        assert_eq!(("no tab", ""), split_line(&test_str));
+        assert_eq!(("no tab", ""), split_line(test_str));
     }
     #[test]
     fn test_one_tab() {
         let test_str = "one\ttab";
-        assert_eq!(("one", "tab"), split_line(&test_str));
+        assert_eq!(("one", "tab"), split_line(test_str));
     }
     #[test]
     fn test_two_tab() {
         let test_str = "one\ttab\tanother";
-        assert_eq!(("one", "tab"), split_line(&test_str));
+        assert_eq!(("one", "tab"), split_line(test_str));
+    }
+}
+
+/// Returns the Levenshtein distance between two strings.
+pub fn edit_distance(left: &str, right: &str) -> usize {
+    let right: Vec<char> = right.chars().collect();
+    let mut previous: Vec<usize> = (0..=right.len()).collect();
+    for (i, left_char) in left.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, right_char) in right.iter().enumerate() {
+            let cost = if left_char == *right_char { 0 } else { 1 };
+            current.push(
+                (previous[j] + cost)
+                    .min(previous[j + 1] + 1)
+                    .min(current[j] + 1),
+            );
+        }
+        previous = current;
+    }
+    previous[right.len()]
+}
+#[cfg(test)]
+mod tests_edit_distance {
+    use super::*;
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("tags", "tags"), 0);
+        assert_eq!(edit_distance("tag", "tags"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+}
+
+/// Returns the base letter(s) of a lower case latin letter with a
+/// diacritic, None if the letter has none.
+fn fold_diacritic(letter: char) -> Option<&'static str> {
+    let folded = match letter {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'æ' => "ae",
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+        'ď' | 'đ' | 'ð' => "d",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+        'ĥ' | 'ħ' => "h",
+        'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => "i",
+        'ĵ' => "j",
+        'ķ' => "k",
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => "l",
+        'ñ' | 'ń' | 'ņ' | 'ň' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => "o",
+        'œ' => "oe",
+        'ŕ' | 'ŗ' | 'ř' => "r",
+        'ś' | 'ŝ' | 'ş' | 'š' => "s",
+        'ß' => "ss",
+        'ţ' | 'ť' | 'ŧ' => "t",
+        'þ' => "th",
+        'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => "u",
+        'ŵ' => "w",
+        'ý' | 'ÿ' | 'ŷ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        _ => return None,
+    };
+    Some(folded)
+}
+
+/// Returns the name in lower case, without diacritics nor punctuation,
+/// the words being separated by a single space.
+/// Apostrophes are dropped so that "Don't" gives "dont".
+pub fn fold_name(name: &str) -> String {
+    let mut folded = String::with_capacity(name.len());
+    let mut separated = true;
+    for letter in name.chars().flat_map(char::to_lowercase) {
+        if letter == '\'' || letter == '\u{2019}' {
+            continue;
+        }
+        if letter.is_alphanumeric() {
+            match fold_diacritic(letter) {
+                Some(base) => folded.push_str(base),
+                None => folded.push(letter),
+            }
+            separated = false;
+        } else if !separated {
+            folded.push(' ');
+            separated = true;
+        }
+    }
+    if folded.ends_with(' ') {
+        folded.pop();
+    }
+    folded
+}
+#[cfg(test)]
+mod tests_fold_name {
+    use super::*;
+    #[test]
+    fn test_fold_name() {
+        assert_eq!(
+            fold_name("AaaaaAAaaaAAAaaAAAAaAAAAA!!! for the Awesome"),
+            "aaaaaaaaaaaaaaaaaaaaaaaaa for the awesome"
+        );
+        assert_eq!(
+            fold_name("  Pokémon: Ætherium Œuvre "),
+            "pokemon aetherium oeuvre"
+        );
+        assert_eq!(fold_name("Don't Starve"), "dont starve");
+        assert_eq!(fold_name("Straße -- ÇA"), "strasse ca");
+        assert_eq!(fold_name("!!!"), "");
+    }
+}
+
+/// Returns the known field closest to the name and their edit distance
+/// (not case sensitive).
+fn closest_field_name(name: &str) -> Option<(usize, &'static str)> {
+    FIELD_NAMES
+        .iter()
+        .map(|field| {
+            (
+                edit_distance(&field.to_lowercase(), &name.to_lowercase()),
+                *field,
+            )
+        })
+        .min()
+}
+
+/// Build the hint given with an unknown field error.
+pub fn unknown_field_hint(name: &str) -> String {
+    match closest_field_name(name) {
+        Some((distance, field)) if distance <= 2 => format!("did you mean {}?", field),
+        _ => format!(
+            "expected one of {} or a capitalized alphanumeric name",
+            FIELD_NAMES.join(", ")
+        ),
     }
 }
 
-pub fn game_dispatch(field: Field, games: &mut ItemCollection<Game>) {
+/// Returns true if the name looks like a misspelled known field
+/// (e.g. "Tag" or "Engin"): one edit away, or two for names of five
+/// characters or more.
+fn is_misspelled_field_name(name: &str) -> bool {
+    match closest_field_name(name) {
+        Some((distance, _)) => distance <= 1 || (distance == 2 && name.chars().count() >= 5),
+        None => false,
+    }
+}
+
+/// Returns true if the name can be used for a field not known by the library
+/// (a capitalized alphanumeric name which is not a misspelled known field).
+pub fn is_extra_field_name(name: &str) -> bool {
+    match name.chars().next() {
+        Some(first) => {
+            first.is_ascii_uppercase()
+                && name.chars().all(|c| c.is_ascii_alphanumeric())
+                && !is_misspelled_field_name(name)
+        }
+        None => false,
+    }
+}
+#[cfg(test)]
+mod tests_extra_field_name {
+    use super::*;
+    #[test]
+    fn test_extra_field_name() {
+        assert!(is_extra_field_name("Players"));
+        assert!(is_extra_field_name("Rating"));
+        assert!(is_extra_field_name("Toto"));
+        assert!(!is_extra_field_name("players"));
+        assert!(!is_extra_field_name("New Field"));
+    }
+    #[test]
+    fn test_misspelled_field_name() {
+        for name in &[
+            "Tag", "Genres", "Engin", "TAGS", "Runtimes", "Publi", "Gnere",
+        ] {
+            assert!(!is_extra_field_name(name), "{}", name);
+        }
+    }
+}
+
+/// A game updated field by field while parsing, either a Game or a
+/// GameRef borrowing its values from the text.
+pub trait ParsedGame<'a>: Default + ItemTraitsMut {
+    fn update_field(&mut self, field: Field<'a>) -> Result<(), ParseError>;
+}
+
+impl<'a> ParsedGame<'a> for Game {
+    fn update_field(&mut self, field: Field<'a>) -> Result<(), ParseError> {
+        self.update(field)
+    }
+}
+
+impl<'a> ParsedGame<'a> for GameRef<'a> {
+    fn update_field(&mut self, field: Field<'a>) -> Result<(), ParseError> {
+        self.update(field)
+    }
+}
+
+/// Updates the current game according to the field.
+/// A Game field starts a new game (with the given id) and the previous
+/// game, now complete, is returned.
+pub fn game_dispatch<'a, G: ParsedGame<'a>>(
+    field: Field<'a>,
+    current: &mut Option<G>,
+    id: usize,
+) -> Result<Option<G>, ParseError> {
     match field {
         Field::NewGame(_) => {
-            let mut game = Game::default();
-            game.set_id(games.count + 1);
-            game.update(field);
-            games.add_item(game);
-        }
-        Field::SingleItem(_, _) | Field::MultipleItems(_, _) => {
-            if let Some(game) = games.items.last_mut() {
-                game.update(field)
+            let mut game = G::default();
+            game.set_id(id);
+            game.update_field(field)?;
+            Ok(current.replace(game))
+        }
+        _ => {
+            match current {
+                Some(game) => game.update_field(field)?,
+                None => return Err(orphan_field_error(&field)),
             };
+            Ok(None)
         }
+    }
+}
+
+/// Returns the error given when a field comes before the first Game field.
+pub fn orphan_field_error(field: &Field) -> ParseError {
+    let name = match field {
+        Field::NewGame(_) => "Game",
+        Field::SingleItem(left, _) | Field::MultipleItems(left, _) | Field::Extra(left, _) => left,
     };
+    ParseError::new(
+        ParseErrorKind::OrphanField,
+        name,
+        "the first field of the database must be a Game field",
+    )
+    .with_column(1)
+}
+#[cfg(test)]
+mod tests_game_dispatch {
+    use super::*;
+    #[test]
+    fn test_game_dispatch() {
+        let mut current: Option<Game> = None;
+        let error = game_dispatch(Field::SingleItem("Engine", "FNA"), &mut current, 1);
+        assert_eq!(error.unwrap_err().kind, ParseErrorKind::OrphanField);
+        let done = game_dispatch(Field::NewGame("Toto"), &mut current, 1).unwrap();
+        assert!(done.is_none());
+        let done = game_dispatch(Field::SingleItem("Engine", "FNA"), &mut current, 1).unwrap();
+        assert!(done.is_none());
+        let done = game_dispatch(Field::NewGame("Titi"), &mut current, 2).unwrap();
+        let done = done.unwrap();
+        assert_eq!(done.id, 1);
+        assert_eq!(done.name, "Toto");
+        assert_eq!(done.engine, "FNA");
+        let current = current.unwrap();
+        assert_eq!(current.id, 2);
+        assert_eq!(current.name, "Titi");
+    }
 }
 
-pub fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
-where
-    P: AsRef<Path>,
-{
+/// Converts a raw line into a string, removing the trailing carriage return if any.
+/// Returns a ParseError (without line number) if the line is not valid UTF-8.
+pub fn decode_line(line: Vec<u8>) -> Result<String, ParseError> {
+    match String::from_utf8(line) {
+        Ok(mut line) => {
+            if line.ends_with('\r') {
+                line.pop();
+            }
+            Ok(line)
+        }
+        Err(error) => Err(ParseError::new(
+            ParseErrorKind::InvalidUtf8,
+            &String::from_utf8_lossy(error.as_bytes()),
+            "the database must be UTF-8 encoded",
+        )
+        .with_column(error.utf8_error().valid_up_to() + 1)),
+    }
+}
+#[cfg(test)]
+mod tests_decode_line {
+    use super::*;
+    #[test]
+    fn test_valid_line() {
+        let line = b"Game\tToto".to_vec();
+        assert_eq!(decode_line(line).unwrap(), "Game\tToto");
+    }
+    #[test]
+    fn test_carriage_return() {
+        let line = b"Game\tToto\r".to_vec();
+        assert_eq!(decode_line(line).unwrap(), "Game\tToto");
+    }
+    #[test]
+    fn test_invalid_line() {
+        let line = b"Game\tTo\xffto".to_vec();
+        let error = decode_line(line).unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::InvalidUtf8);
+        assert_eq!(error.column, Some(8));
+    }
+}
+
+/// Converts a whole database into a string.
+/// Returns a ParseError locating the first invalid byte if the database
+/// is not valid UTF-8.
+pub fn decode_text(bytes: Vec<u8>) -> Result<String, ParseError> {
+    String::from_utf8(bytes).map_err(|error| {
+        let valid = &error.as_bytes()[..error.utf8_error().valid_up_to()];
+        let start = valid
+            .iter()
+            .rposition(|&byte| byte == b'\n')
+            .map_or(0, |index| index + 1);
+        let end = error.as_bytes()[start..]
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .map_or(error.as_bytes().len(), |index| start + index);
+        let line = &error.as_bytes()[start..end];
+        ParseError::new(
+            ParseErrorKind::InvalidUtf8,
+            &String::from_utf8_lossy(line),
+            "the database must be UTF-8 encoded",
+        )
+        .with_line(valid.iter().filter(|&&byte| byte == b'\n').count() + 1)
+        .with_column(valid.len() - start + 1)
+    })
+}
+#[cfg(test)]
+mod tests_decode_text {
+    use super::*;
+    #[test]
+    fn test_invalid_text() {
+        let text = b"Game\tToto\nCover\tTo\xffto\nEngine".to_vec();
+        let error = decode_text(text).unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::InvalidUtf8);
+        assert_eq!(error.line, Some(2));
+        assert_eq!(error.column, Some(9));
+        assert_eq!(error.key, "Cover\tTo\u{fffd}to");
+    }
+}
+
+/// Loads the games from a reader. The filename, if any, is only used
+/// to locate the errors.
+/// In strict mode, the first error is returned and the warnings as diagnostics.
+/// In lenient mode, the problems are returned as diagnostics.
+pub fn load_database_from_reader<R: BufRead>(
+    reader: R,
+    filename: Option<&str>,
+    options: &ParseOptions,
+    games: &mut ItemCollection<Game>,
+) -> Result<Vec<Diagnostic>, Error> {
+    let mut reader = GameReader::with_options(reader, options.clone());
+    if let Some(filename) = filename {
+        reader = reader.with_file(filename);
+    }
+    for game in &mut reader {
+        games.add_item(game?);
+    }
+    Ok(reader.into_diagnostics())
+}
+
+pub fn load_database(
+    filename: &str,
+    options: &ParseOptions,
+    games: &mut ItemCollection<Game>,
+) -> Result<Vec<Diagnostic>, Error> {
     let file = File::open(filename)?;
-    Ok(io::BufReader::new(file).lines())
+    load_database_from_reader(io::BufReader::new(file), Some(filename), options, games)
+}
+
+/// Loads the games of a database written inline, for the tests.
+#[cfg(test)]
+pub fn load_test_games(text: &str) -> ItemCollection<Game> {
+    let mut games: ItemCollection<Game> = ItemCollection::default();
+    load_database_from_reader(text.as_bytes(), None, &ParseOptions::default(), &mut games).unwrap();
+    games
+}
+
+/// # The stable ids of the games of a database
+/// The games whose names give the same id are grouped and told apart by
+/// a discriminator (see `group_stable_ids`), so that the ids of a group
+/// only depend on the games of the group.
+#[derive(Default, PartialEq, Debug)]
+pub struct StableIds {
+    /// The stable id of each game, by position.
+    pub ids: Vec<GameId>,
+    /// The positional id of each game, by stable id.
+    pub index: HashMap<GameId, usize>,
+    /// The positions of the games, by id derived from their name.
+    groups: HashMap<GameId, Vec<usize>>,
+}
+
+impl StableIds {
+    /// Computes the stable ids of the games.
+    pub fn new(games: &ItemCollection<Game>) -> Self {
+        let mut stable_ids = Self::default();
+        for (position, game) in games.items.iter().enumerate() {
+            let id = GameId::from_name(&game.name);
+            stable_ids
+                .groups
+                .entry(id.clone())
+                .or_default()
+                .push(position);
+            stable_ids.ids.push(id);
+        }
+        let names: Vec<GameId> = stable_ids.groups.keys().cloned().collect();
+        for name in names {
+            stable_ids.update_group(games, &name);
+        }
+        stable_ids
+    }
+    /// Adds the id of the game at the position, the last one.
+    pub fn insert(&mut self, games: &ItemCollection<Game>, position: usize) {
+        let name = GameId::from_name(&games.items[position].name);
+        self.ids.push(name.clone());
+        self.groups.entry(name.clone()).or_default().push(position);
+        self.update_group(games, &name);
+    }
+    /// Computes again the ids once the game at the position changed, the
+    /// previous name id being the one of its name before the change.
+    pub fn update(&mut self, games: &ItemCollection<Game>, position: usize, previous: &GameId) {
+        let name = GameId::from_name(&games.items[position].name);
+        if name != *previous {
+            if self.index.get(&self.ids[position]) == Some(&games.items[position].id) {
+                self.index.remove(&self.ids[position]);
+            }
+            move_position(&mut self.groups, previous, name.clone(), position);
+            self.update_group(games, previous);
+        }
+        self.update_group(games, &name);
+    }
+    /// Computes again the ids of the games having the given name id.
+    fn update_group(&mut self, games: &ItemCollection<Game>, name: &GameId) {
+        let positions = match self.groups.get(name) {
+            Some(positions) => positions,
+            None => return,
+        };
+        let group: Vec<&Game> = positions
+            .iter()
+            .map(|&position| &games.items[position])
+            .collect();
+        // the old ids are all removed first as they can be given to
+        // another game of the group
+        for (&position, game) in positions.iter().zip(&group) {
+            if self.index.get(&self.ids[position]) == Some(&game.id) {
+                self.index.remove(&self.ids[position]);
+            }
+        }
+        for ((&position, game), id) in positions
+            .iter()
+            .zip(&group)
+            .zip(group_stable_ids(name, &group))
+        {
+            self.index.insert(id.clone(), game.id);
+            self.ids[position] = id;
+        }
+    }
 }
 
-pub fn load_database(filename: &str, games: &mut ItemCollection<Game>) {
-    if let Ok(lines) = read_lines(filename) {
-        for line in lines.flatten() {
-            game_dispatch(Field::from(&line), games);
+/// Moves a position of an index from one key to another one, keeping
+/// the positions in increasing order.
+pub fn move_position<K: Eq + Hash>(
+    index: &mut HashMap<K, Vec<usize>>,
+    from: &K,
+    into: K,
+    position: usize,
+) {
+    if let Some(positions) = index.get_mut(from) {
+        positions.retain(|other| *other != position);
+        if positions.is_empty() {
+            index.remove(from);
         }
     }
+    let positions = index.entry(into).or_default();
+    if let Err(insert) = positions.binary_search(&position) {
+        positions.insert(insert, position);
+    }
 }
 
-pub fn load_tags_from_games(tags: &mut ItemCollection<Item>, games: &ItemCollection<Game>) {
+/// Returns the stable ids of games having the same name id, in the same
+/// order. A game alone keeps the name id. Otherwise, each game is told
+/// apart by its IGDB id ("toto--igdb1234"), or else by its year
+/// ("toto--2011"). The games left with the same discriminator, or without
+/// any, are numbered in the database order ("toto--2011-1", "toto--game-1").
+/// As the name part of an id never contains two dashes in a row, these ids
+/// cannot be the id of another name.
+pub fn group_stable_ids(name: &GameId, games: &[&Game]) -> Vec<GameId> {
+    if games.len() == 1 {
+        return vec![name.clone()];
+    }
+    let discriminators: Vec<String> = games
+        .iter()
+        .map(|game| match &game.igdb_id {
+            Some(igdb_id) => format!("igdb{}", igdb_id.id()),
+            None if game.year.len() == 4 && game.year.chars().all(|c| c.is_ascii_digit()) => {
+                game.year.clone()
+            }
+            None => "game".to_string(),
+        })
+        .collect();
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for discriminator in &discriminators {
+        *counts.entry(discriminator).or_insert(0) += 1;
+    }
+    let mut numbers: HashMap<&str, usize> = HashMap::new();
+    discriminators
+        .iter()
+        .map(|discriminator| {
+            if counts[discriminator.as_str()] == 1 && discriminator != "game" {
+                return name.with_discriminator(discriminator);
+            }
+            let number = numbers.entry(discriminator).or_insert(0);
+            *number += 1;
+            name.with_discriminator(&format!("{} {}", discriminator, number))
+        })
+        .collect()
+}
+#[cfg(test)]
+mod tests_stable_ids {
+    use super::*;
+    use models::IgdbId;
+    fn collection(games: &[(&str, &str, Option<u64>)]) -> ItemCollection<Game> {
+        let mut collection: ItemCollection<Game> = ItemCollection::default();
+        for (name, year, igdb_id) in games {
+            let mut game = Game::new();
+            game.name = name.to_string();
+            game.year = year.to_string();
+            game.igdb_id = igdb_id.map(IgdbId::new);
+            collection.add_item(game);
+        }
+        collection
+    }
+    fn ids(games: &ItemCollection<Game>) -> Vec<String> {
+        let stable_ids = StableIds::new(games);
+        games
+            .items
+            .iter()
+            .enumerate()
+            .map(|(position, game)| {
+                assert_eq!(stable_ids.index[&stable_ids.ids[position]], game.id);
+                stable_ids.ids[position].to_string()
+            })
+            .collect()
+    }
+    #[test]
+    fn test_same_names() {
+        let games = collection(&[
+            ("Toto", "2011", None),
+            ("Toto 2", "", None),
+            ("Toto", "2014", None),
+            ("toto", "", Some(12)),
+        ]);
+        assert_eq!(
+            ids(&games),
+            vec!["toto--2011", "toto-2", "toto--2014", "toto--igdb12"]
+        );
+    }
+    #[test]
+    fn test_no_collision() {
+        let games = collection(&[("Toto", "", None), ("Toto", "", None), ("Toto 2", "", None)]);
+        assert_eq!(ids(&games), vec!["toto--game-1", "toto--game-2", "toto-2"]);
+        let games = collection(&[
+            ("Toto", "2", None),
+            ("Toto", "", None),
+            ("Toto-Game 1", "", None),
+        ]);
+        assert_eq!(
+            ids(&games),
+            vec!["toto--game-1", "toto--game-2", "toto-game-1"]
+        );
+    }
+    #[test]
+    fn test_reordered() {
+        let games = [
+            ("Toto", "2011", None),
+            ("Titi", "", None),
+            ("Toto", "2014", Some(12)),
+            ("Toto", "2014", None),
+        ];
+        let reordered = [games[3], games[2], games[1], games[0]];
+        let found = ids(&collection(&games));
+        assert_eq!(
+            found,
+            vec!["toto--2011", "titi", "toto--igdb12", "toto--2014"]
+        );
+        let mut found_reordered = ids(&collection(&reordered));
+        found_reordered.reverse();
+        assert_eq!(found, found_reordered);
+    }
+    #[test]
+    fn test_update() {
+        let mut games = collection(&[
+            ("Toto", "2011", None),
+            ("Titi", "", None),
+            ("Toto", "2014", None),
+        ]);
+        let mut stable_ids = StableIds::new(&games);
+        games.items[1].name = "Toto".to_string();
+        stable_ids.update(&games, 1, &GameId::from_name("Titi"));
+        assert_eq!(stable_ids, StableIds::new(&games));
+        games.items[0].name = "Tata".to_string();
+        stable_ids.update(&games, 0, &GameId::from_name("Toto"));
+        assert_eq!(stable_ids, StableIds::new(&games));
+        games.items[2].year = String::new();
+        stable_ids.update(&games, 2, &GameId::from_name("Toto"));
+        assert_eq!(stable_ids, StableIds::new(&games));
+        games.add_item(Game::new());
+        games.items[3].name = "Tata".to_string();
+        stable_ids.insert(&games, 3);
+        assert_eq!(stable_ids, StableIds::new(&games));
+        let id: GameId = "tata--game-1".parse().unwrap();
+        assert_eq!(stable_ids.index[&id], 4);
+    }
+}
+
+/// Adds to the items the values of the games, each item keeping the ids of
+/// the games having its value. A game having a value twice is added once.
+pub fn load_items_from_games<T, F>(
+    items: &mut ItemCollection<Item>,
+    games: &ItemCollection<T>,
+    values: F,
+) where
+    T: ItemTraits,
+    F: Fn(&T) -> Vec<String>,
+{
     for game in &games.items {
-        if !game.tags.is_empty() {
-            for tag in &game.tags {
-                match tags.get_item_by_name_mut(tag) {
-                    Some(tag_item) => tag_item.games.push(game.id),
-                    None => {
-                        let mut newtag = Item::new();
-                        newtag.name = tag.to_string();
-                        newtag.games.push(game.id);
-                        tags.items.push(newtag);
-                    }
+        for value in values(game) {
+            if let Some(mut item) = items.get_item_by_name_mut(&value) {
+                if item.games.last() != Some(&game.get_id()) {
+                    item.games.push(game.get_id());
                 }
+                continue;
             }
+            let mut item = Item::new();
+            item.name = value;
+            item.games.push(game.get_id());
+            items.add_item(item);
         }
     }
 }
@@ -127,9 +741,12 @@ mod tests_load_tags {
         let mut game = Game::new();
         game.tags = vec!["tag1".to_string()];
         gamecollection.add_item(game);
-        load_tags_from_games(&mut tagcollection, &gamecollection);
+        load_items_from_games(&mut tagcollection, &gamecollection, |game| {
+            game.tags.clone()
+        });
         // Check if the number of tags is correct
         assert_eq!(tagcollection.items.len(), 3);
+        assert_eq!(tagcollection.count, 3);
         // Check if the tag names are correct
         assert_eq!(tagcollection.items[0].name, "tag1".to_string());
         assert_eq!(tagcollection.items[1].name, "tag2".to_string());
@@ -141,23 +758,6 @@ mod tests_load_tags {
     }
 }
 
-pub fn load_genres_from_games(genres: &mut ItemCollection<Item>, games: &ItemCollection<Game>) {
-    for game in &games.items {
-        if !game.genres.is_empty() {
-            for genre in &game.genres {
-                match genres.get_item_by_name_mut(genre) {
-                    Some(genre_item) => genre_item.games.push(game.id),
-                    None => {
-                        let mut newgenre = Item::new();
-                        newgenre.name = genre.to_string();
-                        newgenre.games.push(game.id);
-                        genres.items.push(newgenre);
-                    }
-                }
-            }
-        }
-    }
-}
 #[cfg(test)]
 mod tests_load_genres {
     use super::*;
@@ -176,9 +776,12 @@ mod tests_load_genres {
         let mut game = Game::new();
         game.genres = vec!["gen1".to_string()];
         gamecollection.add_item(game);
-        load_genres_from_games(&mut genrecollection, &gamecollection);
+        load_items_from_games(&mut genrecollection, &gamecollection, |game| {
+            game.genres.clone()
+        });
         // Check if the number of tags is correct
         assert_eq!(genrecollection.items.len(), 3);
+        assert_eq!(genrecollection.count, 3);
         // Check if the tag names are correct
         assert_eq!(genrecollection.items[0].name, "gen1".to_string());
         assert_eq!(genrecollection.items[1].name, "gen2".to_string());
@@ -1,9 +1,18 @@
 extern crate pobsdlib;
-use pobsdlib::collections::DataBase;
+extern crate serde_json;
+use pobsdlib::collections::{DataBase, DataBaseBuffer};
+use pobsdlib::export::{source_hash, Export, Sections, SCHEMA_VERSION};
+use pobsdlib::facets::Facet;
+use pobsdlib::models::{Date, GameId};
+use pobsdlib::parser::{GameReader, ParseMode, ParseOptions, Severity};
+use pobsdlib::query::Query;
+use pobsdlib::search::SearchIndex;
+use pobsdlib::sorting::{SortKey, SortOrder};
+use pobsdlib::{Error, ParseErrorKind};
 
 #[test]
 fn test_game_get_by_id() {
-    let db_game = DataBase::new("tests/data/test-games.db");
+    let db_game = DataBase::open("tests/data/test-games.db").unwrap();
     match db_game.games.get_item_by_id(2) {
         Some(game) => {
             assert_eq!(game.name, "The Adventures of Shuggy".to_string());
@@ -19,8 +28,18 @@ fn test_game_get_by_id() {
     }
 }
 #[test]
+fn test_game_get_by_id_out_of_range() {
+    let db_game = DataBase::open("tests/data/test-games.db").unwrap();
+    assert!(db_game.get_game_by_id(0).is_none());
+    assert!(db_game.get_game_by_id(9).is_none());
+    let indie = db_game.get_games_by_tag("indie");
+    let id = indie.items[1].id;
+    assert_eq!(indie.get_item_by_id(id).unwrap().id, id);
+    assert!(indie.get_item_by_id(0).is_none());
+}
+#[test]
 fn test_game_get_by_name() {
-    let db_game = DataBase::new("tests/data/test-games.db");
+    let db_game = DataBase::open("tests/data/test-games.db").unwrap();
     match db_game.games.get_item_by_name("Akane the Kunoichi") {
         Some(game) => {
             assert_eq!(game.id, 6);
@@ -31,7 +50,7 @@ fn test_game_get_by_name() {
 }
 #[test]
 fn test_game_get_by_tag() {
-    let db_game = DataBase::new("tests/data/test-games.db");
+    let db_game = DataBase::open("tests/data/test-games.db").unwrap();
     let games = db_game.get_games_by_tag("indie");
     assert_eq!(games.count, 2);
     assert_eq!(games.items[0].name, "The Adventures of Shuggy".to_string());
@@ -39,9 +58,406 @@ fn test_game_get_by_tag() {
 }
 #[test]
 fn test_game_get_by_genre() {
-    let db_game = DataBase::new("tests/data/test-games.db");
+    let db_game = DataBase::open("tests/data/test-games.db").unwrap();
     let games = db_game.get_games_by_genre("RPG");
     assert_eq!(games.count, 2);
     assert_eq!(games.items[0].name, "Aedemphia".to_string());
     assert_eq!(games.items[1].name, "Always Sometimes Monsters".to_string());
 }
+#[test]
+fn test_open_unknown_field() {
+    match DataBase::open("tests/data/test-unknown-field.db") {
+        Ok(_) => panic!("Database should not be loaded"),
+        Err(Error::Parse(error)) => {
+            assert_eq!(error.kind, ParseErrorKind::UnknownField);
+            assert_eq!(
+                error.file,
+                Some("tests/data/test-unknown-field.db".to_string())
+            );
+            assert_eq!(error.line, Some(4));
+            assert_eq!(error.column, Some(1));
+            assert_eq!(error.key, "tag".to_string());
+            assert_eq!(error.hint, "did you mean Tags?".to_string());
+        }
+        Err(error) => panic!("Unexpected error: {}", error),
+    }
+}
+#[test]
+fn test_open_missing_file() {
+    match DataBase::open("tests/data/missing.db") {
+        Err(Error::Io(error)) => assert_eq!(error.kind(), std::io::ErrorKind::NotFound),
+        _ => panic!("Should be an io error"),
+    }
+}
+#[test]
+fn test_open_invalid_utf8() {
+    match DataBase::open("tests/data/test-invalid-utf8.db") {
+        Err(Error::Parse(error)) => {
+            assert_eq!(error.kind, ParseErrorKind::InvalidUtf8);
+            assert_eq!(error.line, Some(2));
+            assert_eq!(error.column, Some(10));
+        }
+        _ => panic!("Should be a parse error"),
+    }
+}
+#[test]
+#[allow(deprecated)]
+fn test_new_missing_file() {
+    let db_game = DataBase::new("tests/data/missing.db");
+    assert_eq!(db_game.get_games_count(), 0);
+}
+#[test]
+#[allow(deprecated)]
+fn test_new_lenient() {
+    let db_game = DataBase::new("tests/data/test-unknown-field.db");
+    assert!(DataBase::open("tests/data/test-unknown-field.db").is_err());
+    assert_eq!(db_game.get_games_count(), 1);
+    assert_eq!(db_game.get_tags_count(), 0);
+}
+#[test]
+fn test_from_reader() {
+    let file = std::fs::File::open("tests/data/test-games.db").unwrap();
+    let db_game = DataBase::from_reader(file).unwrap();
+    let db_game_bis = DataBase::open("tests/data/test-games.db").unwrap();
+    assert_eq!(db_game.get_games_count(), db_game_bis.get_games_count());
+    assert_eq!(db_game.get_tags_count(), db_game_bis.get_tags_count());
+    assert_eq!(db_game.get_genres_count(), db_game_bis.get_genres_count());
+}
+#[test]
+fn test_from_str_error() {
+    match "Game\tToto\ntag\tindie".parse::<DataBase>() {
+        Err(Error::Parse(error)) => {
+            assert_eq!(error.file, None);
+            assert_eq!(error.line, Some(2));
+        }
+        _ => panic!("Should be a parse error"),
+    }
+}
+#[test]
+fn test_write_round_trip() {
+    let input = std::fs::read("tests/data/test-games.db").unwrap();
+    let db_game = DataBase::open("tests/data/test-games.db").unwrap();
+    let mut output: Vec<u8> = Vec::new();
+    db_game.write_to(&mut output).unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        String::from_utf8(input).unwrap()
+    );
+}
+#[test]
+fn test_open_lenient() {
+    let options = ParseOptions {
+        mode: ParseMode::Lenient,
+    };
+    let (db_game, diagnostics) =
+        DataBase::open_with_options("tests/data/test-malformed.db", &options).unwrap();
+    assert_eq!(db_game.get_games_count(), 2);
+    assert_eq!(db_game.games.items[0].cover, "aeternum.jpg".to_string());
+    assert_eq!(db_game.games.items[0].engine, "FNA".to_string());
+    assert_eq!(db_game.games.items[1].year, "2011".to_string());
+    let lines: Vec<usize> = diagnostics.iter().map(|d| d.line).collect();
+    assert_eq!(lines, vec![1, 3, 5, 7]);
+    let kinds: Vec<ParseErrorKind> = diagnostics.iter().map(|d| d.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            ParseErrorKind::OrphanField,
+            ParseErrorKind::TooManyTabs,
+            ParseErrorKind::UnknownField,
+            ParseErrorKind::UnknownField
+        ]
+    );
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert_eq!(diagnostics[1].severity, Severity::Warning);
+    assert_eq!(diagnostics[2].severity, Severity::Error);
+}
+#[test]
+fn test_open_strict() {
+    match DataBase::open("tests/data/test-malformed.db") {
+        Err(Error::Parse(error)) => {
+            assert_eq!(error.kind, ParseErrorKind::OrphanField);
+            assert_eq!(error.line, Some(1));
+        }
+        _ => panic!("Should be a parse error"),
+    }
+}
+#[test]
+fn test_extra_fields_round_trip() {
+    let input = std::fs::read_to_string("tests/data/test-extra-fields.db").unwrap();
+    let db_game = DataBase::open("tests/data/test-extra-fields.db").unwrap();
+    let game = db_game.get_game_by_id(1).unwrap();
+    assert_eq!(game.extra.get("Rating"), Some("good"));
+    assert_eq!(game.extra.get("Players"), Some("1-2"));
+    assert_eq!(db_game.games.get_item_with_field("Rating", "good").count, 1);
+    let mut output: Vec<u8> = Vec::new();
+    db_game.write_to(&mut output).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), input);
+    let json = serde_json::to_string(&db_game.games).unwrap();
+    assert!(json.contains(r#""extra":{"Rating":"good","Players":"1-2"}"#));
+}
+#[test]
+fn test_dated_fields() {
+    let db_game = DataBase::open("tests/data/test-extra-fields.db").unwrap();
+    let game = db_game.get_game_by_id(1).unwrap();
+    assert_eq!(game.added, Date::new(2022, 1, 1));
+    assert_eq!(game.updated, Date::new(2023, 2, 3));
+    assert_eq!(game.igdb_id.as_ref().map(|id| id.id()), Some(1234));
+    assert_eq!(db_game.games.get_item_with_field("IgdbId", "1234").count, 1);
+    let json = serde_json::to_string(&db_game.games).unwrap();
+    assert!(json.contains(r#""added":"2022-01-01","updated":"2023-02-03","igdb_id":1234"#));
+}
+#[test]
+fn test_game_reader() {
+    let file = std::fs::File::open("tests/data/test-games.db").unwrap();
+    let reader = GameReader::new(std::io::BufReader::new(file));
+    let db_game = DataBase::open("tests/data/test-games.db").unwrap();
+    let mut count = 0;
+    for game in reader {
+        let game = game.unwrap();
+        assert_eq!(db_game.get_game_by_id(game.id), Some(&game));
+        count += 1;
+    }
+    assert_eq!(count, db_game.get_games_count());
+}
+#[test]
+fn test_buffer_games() {
+    let buffer = DataBaseBuffer::open("tests/data/test-games.db").unwrap();
+    let games = buffer.games().unwrap();
+    let db_game = DataBase::open("tests/data/test-games.db").unwrap();
+    assert_eq!(games.count, db_game.get_games_count());
+    for game in &games.items {
+        assert_eq!(
+            db_game.get_game_by_id(game.id),
+            Some(&game.to_game().unwrap())
+        );
+    }
+    let rpg = games.get_item_with_genre("RPG");
+    assert_eq!(rpg.count, 2);
+    assert_eq!(rpg.items[0].name, "Aedemphia");
+}
+#[test]
+fn test_buffer_invalid_utf8() {
+    match DataBaseBuffer::open("tests/data/test-invalid-utf8.db") {
+        Err(Error::Parse(error)) => {
+            assert_eq!(error.kind, ParseErrorKind::InvalidUtf8);
+            assert_eq!(error.line, Some(2));
+            assert_eq!(error.column, Some(10));
+        }
+        _ => panic!("Should be a parse error"),
+    }
+}
+#[test]
+fn test_from_json_round_trip() {
+    let db_game = DataBase::open("tests/data/test-extra-fields.db").unwrap();
+    let json = serde_json::to_string(&db_game).unwrap();
+    let db_json = DataBase::from_json(&json).unwrap();
+    assert_eq!(db_json.games.items, db_game.games.items);
+    assert_eq!(db_json.tags.items, db_game.tags.items);
+    assert_eq!(db_json.genres.items, db_game.genres.items);
+}
+#[test]
+fn test_from_json_games_collection() {
+    let db_game = DataBase::open("tests/data/test-games.db").unwrap();
+    let json = serde_json::to_string_pretty(&db_game.games).unwrap();
+    let db_json = DataBase::from_json(&json).unwrap();
+    assert_eq!(db_json.games.items, db_game.games.items);
+    assert_eq!(db_json.get_tags_count(), db_game.get_tags_count());
+    assert_eq!(db_json.get_games_by_tag("indie").count, 2);
+}
+#[test]
+fn test_from_json_inconsistent() {
+    let db_game = DataBase::open("tests/data/test-games.db").unwrap();
+    let mut db_json = serde_json::to_value(&db_game).unwrap();
+    db_json["tags"]["items"][0]["games"] = serde_json::json!([3]);
+    match DataBase::from_json(&db_json.to_string()) {
+        Err(Error::Json(error)) => assert!(error.to_string().contains("does not reference")),
+        _ => panic!("Should be a json error"),
+    }
+    let mut db_json = serde_json::to_value(&db_game).unwrap();
+    db_json["games"]["items"][1]["id"] = serde_json::json!(5);
+    assert!(DataBase::from_json(&db_json.to_string()).is_err());
+    assert!(DataBase::from_json("{\"tags\": []}").is_err());
+}
+#[test]
+fn test_export_round_trip() {
+    let db_game = DataBase::open("tests/data/test-games.db").unwrap();
+    let file = std::fs::File::open("tests/data/test-games.db").unwrap();
+    let hash = source_hash(file).unwrap();
+    let export = Export::new(&db_game, Sections::default()).with_source_hash(&hash);
+    let json = serde_json::to_string(&export).unwrap();
+    let db_json = DataBase::from_json(&json).unwrap();
+    assert_eq!(db_json.games.items, db_game.games.items);
+    assert_eq!(db_json.tags.items, db_game.tags.items);
+    assert_eq!(db_json.genres.items, db_game.genres.items);
+}
+#[test]
+fn test_export_checks() {
+    let db_game = DataBase::open("tests/data/test-games.db").unwrap();
+    let mut json = serde_json::to_value(Export::new(&db_game, Sections::default())).unwrap();
+    json["game_count"] = serde_json::json!(3);
+    assert!(DataBase::from_json(&json.to_string()).is_err());
+    json["game_count"] = serde_json::json!(8);
+    json["schema_version"] = serde_json::json!(SCHEMA_VERSION + 1);
+    assert!(DataBase::from_json(&json.to_string()).is_err());
+    let sections = Sections {
+        games: false,
+        ..Default::default()
+    };
+    let json = serde_json::to_string(&Export::new(&db_game, sections)).unwrap();
+    assert!(DataBase::from_json(&json).is_err());
+}
+#[test]
+fn test_stable_ids_survive_insertion() {
+    let old = DataBase::open("tests/data/test-games.db").unwrap();
+    let text = std::fs::read_to_string("tests/data/test-games.db").unwrap();
+    let new: DataBase = format!("Game\tA New Game\nTags\tindie\n{}", text)
+        .parse()
+        .unwrap();
+    let migration = old.migrate_ids(&new);
+    assert_eq!(migration.added, vec![1]);
+    assert!(migration.removed.is_empty());
+    for game in &old.games.items {
+        assert_eq!(migration.get(game.id), Some(game.id + 1));
+        let stable_id = old.get_game_stable_id(game.id).unwrap();
+        assert_eq!(
+            new.get_game_by_stable_id(stable_id).unwrap().name,
+            game.name
+        );
+    }
+    let id: GameId = "the-adventures-of-shuggy".parse().unwrap();
+    assert_eq!(old.get_game_by_stable_id(&id).unwrap().id, 2);
+    assert_eq!(new.get_game_by_stable_id(&id).unwrap().id, 3);
+}
+#[test]
+fn test_migration_by_igdb_id() {
+    let old: DataBase = "Game\tToto\nIgdbId\t12\nGame\tTiti".parse().unwrap();
+    let new: DataBase = "Game\tTiti\nGame\tToto: Remastered\nIgdbId\t12"
+        .parse()
+        .unwrap();
+    let migration = old.migrate_ids(&new);
+    assert_eq!(migration.get(1), Some(2));
+    assert_eq!(migration.get(2), Some(1));
+    assert_eq!(old.get_game_stable_id(0), None);
+}
+#[test]
+fn test_games_by_query() {
+    let db_game = DataBase::open("tests/data/test-games.db").unwrap();
+    let query: Query = "(tag:indie OR genre:rpg) AND year<=2014".parse().unwrap();
+    let games = db_game.get_games_by_query(&query);
+    let names: Vec<&str> = games.items.iter().map(|game| game.name.as_str()).collect();
+    // Aedemphia has no year
+    assert_eq!(
+        names,
+        vec!["The Adventures of Shuggy", "Always Sometimes Monsters"]
+    );
+    let buffer = DataBaseBuffer::open("tests/data/test-games.db").unwrap();
+    let games_ref = buffer.games().unwrap();
+    assert_eq!(games_ref.get_item_with_query(&query).count, 2);
+}
+#[test]
+fn test_search_games() {
+    let db_game = DataBase::open("tests/data/test-games.db").unwrap();
+    let results = db_game.search_games("Akane platformer");
+    assert_eq!(results[0].item.name, "Akane the Kunoichi");
+    assert_eq!(results[0].fields, vec!["Game", "Genre"]);
+    let names: Vec<&str> = results.iter().map(|r| r.item.name.as_str()).collect();
+    assert!(names.contains(&"The Adventures of Shuggy"));
+    let buffer = DataBaseBuffer::open("tests/data/test-games.db").unwrap();
+    let games = buffer.games().unwrap();
+    let index = SearchIndex::new(&games);
+    assert_eq!(
+        index.search("Akane platformer")[0].item.name,
+        "Akane the Kunoichi"
+    );
+}
+#[test]
+fn test_find_games_fuzzy() {
+    let db_game = DataBase::open("tests/data/test-games.db").unwrap();
+    let found = db_game.find_games_fuzzy("akane the kunoichi", 5);
+    assert_eq!(found[0].item.name, "Akane the Kunoichi");
+    assert_eq!(found[0].similarity, 1.0);
+    let found = db_game.find_games_fuzzy("Adventures of Shugy", 1);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].item.name, "The Adventures of Shuggy");
+    assert!(db_game.find_games_fuzzy("zzzzzzzz", 5).is_empty());
+    assert_eq!(
+        db_game.suggest_genre("platformers").unwrap().name,
+        "Platformer"
+    );
+}
+#[test]
+fn test_complete() {
+    let db_game = DataBase::open("tests/data/test-games.db").unwrap();
+    let completions = db_game.complete("Game", "the adv", 5);
+    assert_eq!(completions.len(), 1);
+    assert_eq!(completions[0].value, "The Adventures of Shuggy");
+    let tags = db_game.complete("tags", "in", 5);
+    assert_eq!(tags[0].value, "indie");
+    assert_eq!(tags[0].count, db_game.get_games_by_tag("indie").count);
+    assert_eq!(db_game.complete("Engine", "", 2)[0].count, 2);
+}
+#[test]
+fn test_sort_and_paginate() {
+    let db_game = DataBase::open("tests/data/test-games.db").unwrap();
+    let mut games = db_game.get_games_by_query(&Query::parse("year>=2014").unwrap());
+    games.sort_items(SortKey::Year, SortOrder::Ascending);
+    let names: Vec<&str> = games.items.iter().map(|game| game.name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "Akane the Kunoichi",
+            "Always Sometimes Monsters",
+            "Amazing Princess Sarah",
+            "Aeternum",
+            "Airships: Conquer the Skies"
+        ]
+    );
+    let mut games = db_game.games.get_item_with_field("Game", "");
+    games.sort_items(SortKey::Name, SortOrder::Descending);
+    assert_eq!(games.get_item_by_name("Aeternum").unwrap().id, 4);
+    let page = games.into_page(6, 5);
+    assert_eq!(page.total, 8);
+    assert_eq!(page.count, 2);
+    assert_eq!(page.items[0].name, "The Adventures of Shuggy");
+    assert_eq!(
+        page.items[1].name,
+        "AaaaaAAaaaAAAaaAAAAaAAAAA!!! for the Awesome"
+    );
+}
+#[test]
+fn test_facets() {
+    let db_game = DataBase::open("tests/data/test-games.db").unwrap();
+    let games = db_game.games.get_item_with_field("Game", "");
+    let facets = games.get_facets();
+    for tag in &facets.tags {
+        let item = db_game.tags.get_item_by_name(&tag.value).unwrap();
+        assert_eq!(tag.count, item.games.len());
+    }
+    assert_eq!(facets.engine[0].value, "FNA");
+    assert_eq!(facets.engine[0].count, 2);
+    assert_eq!(facets.store[0].value, "store.steampowered.com");
+    assert_eq!(facets.store[0].count, 5);
+    assert_eq!(facets.decade[0].count, 7);
+    let subset = db_game.get_games_by_query(&Query::parse("engine:xna").unwrap());
+    assert_eq!(subset.get_facet(Facet::Year)[0].value, "2014");
+}
+#[test]
+fn test_navigation() {
+    let db_game = DataBase::open("tests/data/test-games.db").unwrap();
+    let tags = db_game.tags.get_name_counts();
+    assert_eq!(tags[0], ("indie", 2));
+    assert_eq!(tags.len(), db_game.get_tags_count());
+    let indie = db_game.tags.get_item_by_name("indie").unwrap();
+    let games = db_game.games_of(indie);
+    assert_eq!(games.count, 2);
+    for game in &games.items {
+        assert!(db_game.tags_of(game).iter().any(|tag| tag.id == indie.id));
+    }
+    let shuggy = db_game
+        .get_game_by_name("The Adventures of Shuggy")
+        .unwrap();
+    let genres = db_game.genres_of(shuggy);
+    assert_eq!(genres[0].name, "Puzzle Platformer");
+    assert!(db_game.get_genre_names().items.contains(&"RPG"));
+    assert_eq!(db_game.get_genre_names().count, db_game.get_genres_count());
+}